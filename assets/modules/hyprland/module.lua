@@ -1,5 +1,7 @@
 local M = {}
 
+M.fast_reload = true
+
 local template = niji.Template:load("theme.conf.mustache")
 
 function M.apply(config, theme)
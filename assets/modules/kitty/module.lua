@@ -1,11 +1,23 @@
 local M = {}
 
+M.fast_reload = true
+
 local template = niji.Template:load("niji.conf.mustache")
 
 function M.apply(config, theme)
+	-- kitty supports the full 256 color palette, the first 16 of which are set separately
+	local extended_colors = {}
+	for i = 17, #theme.terminal.palette_256 do
+		table.insert(extended_colors, { index = i - 1, color = theme.terminal.palette_256[i] })
+	end
+
 	local theme = template:render {
-		background = theme.ui.background,
-		foreground = theme.ui.text_background,
+		background = theme.terminal.background,
+		foreground = theme.terminal.foreground,
+		cursor = theme.terminal.cursor,
+		cursor_text = theme.terminal.cursor_text,
+		selection_background = theme.terminal.selection_background,
+		selection_foreground = theme.terminal.selection_foreground,
 		url = theme.ui.secondary,
 		alert = theme.ui.warning,
 		primary = theme.ui.primary,
@@ -27,7 +39,8 @@ function M.apply(config, theme)
 		bright_blue = theme.terminal.bright_blue,
 		bright_magenta = theme.terminal.bright_magenta,
 		bright_cyan = theme.terminal.bright_cyan,
-		bright_white = theme.terminal.bright_white
+		bright_white = theme.terminal.bright_white,
+		extended_colors = extended_colors
 	}
 
 	niji.fs.write_config("kitty/themes/niji.conf", theme)
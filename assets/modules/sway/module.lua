@@ -1,5 +1,7 @@
 local M = {}
 
+M.fast_reload = true
+
 local template = niji.Template:load("theme.mustache")
 
 function M.apply(config, theme)
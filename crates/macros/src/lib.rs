@@ -2,17 +2,19 @@ use proc_macro::TokenStream;
 use proc_macro_error::proc_macro_error;
 use proc_macro_error::{abort, abort_call_site};
 use quote::quote;
-use syn::*;
+use syn::{ext::IdentExt, *};
 
 extern crate proc_macro;
 
 enum LuaAttr {
-	With(Path)
+	With(Path),
+	Skip
 }
 
 impl LuaAttr {
 	fn parse(attr: &Attribute) -> Option<Self> {
 		match &attr.meta {
+			Meta::Path(path) if path.is_ident("lua_skip") => Some(Self::Skip),
 			Meta::List(meta_list) => {
 				if meta_list.path.is_ident("lua_with") {
 					let Ok(path) = parse2::<LitStr>(meta_list.tokens.clone())
@@ -82,7 +84,7 @@ fn derive_into_lua_enum(name: Ident, data: DataEnum) -> TokenStream {
 					Some(LuaAttr::With(path)) => quote! {
 						Self::#name(value) => mlua::IntoLua::into_lua(#path(&value), lua)
 					},
-					None => quote! {
+					_ => quote! {
 						Self::#name(value) => mlua::IntoLua::into_lua(value, lua)
 					}
 				}
@@ -103,19 +105,24 @@ fn derive_into_lua_enum(name: Ident, data: DataEnum) -> TokenStream {
 }
 
 fn derive_into_lua_struct(name: Ident, data: DataStruct) -> TokenStream {
-	let field_names: Vec<&Ident> = data
+	let fields: Vec<&Field> = data
 		.fields
+		.iter()
+		.filter(|f| !matches!(get_lua_attr(&f.attrs), Some(LuaAttr::Skip)))
+		.collect();
+
+	// Raw identifiers like `r#type` are exposed to Lua without their prefix
+	let field_names: Vec<String> = fields
 		.iter()
 		.map(|f| {
 			let Some(field_ident) = &f.ident else {
 				abort!(f, "Unnamed struct fields are not supported at the moment");
 			};
-			field_ident
+			field_ident.unraw().to_string()
 		})
 		.collect();
 
-	let fields_into_lua: Vec<proc_macro2::TokenStream> = data
-		.fields
+	let fields_into_lua: Vec<proc_macro2::TokenStream> = fields
 		.iter()
 		.map(|f| {
 			let lua_attr = get_lua_attr(&f.attrs);
@@ -137,7 +144,7 @@ fn derive_into_lua_struct(name: Ident, data: DataStruct) -> TokenStream {
 			fn into_lua(self, lua: &'lua mlua::Lua) -> mlua::Result<mlua::Value<'lua>> {
 				let table = lua.create_table()?;
 
-				#(table.raw_set(stringify!(#field_names), #fields_into_lua?)?;)*
+				#(table.raw_set(#field_names, #fields_into_lua?)?;)*
 
 				table.into_lua(lua)
 			}
@@ -146,7 +153,7 @@ fn derive_into_lua_struct(name: Ident, data: DataStruct) -> TokenStream {
 	.into()
 }
 
-#[proc_macro_derive(IntoLua, attributes(lua_with))]
+#[proc_macro_derive(IntoLua, attributes(lua_with, lua_skip))]
 #[proc_macro_error]
 pub fn derive_into_lua(input: TokenStream) -> TokenStream {
 	let ast: DeriveInput = parse(input).unwrap();
@@ -1,13 +1,30 @@
-use std::rc::Rc;
+use std::{
+	collections::BTreeMap,
+	path::{Path, PathBuf},
+	rc::Rc,
+	time::Duration
+};
 
+use chrono::{DateTime, Local, NaiveDate};
+use log::debug;
 use thiserror::Error;
 
 use crate::{
-	config::{self, Config, Theme},
+	config::{self, ColorScheme, Config, ModuleConfig, Schedule, Theme},
+	config_manager::{self, ConfigManager},
 	file_manager::{self, FileManager},
 	files::{self, Files},
 	module_manager::{self, ModuleManager, ModuleManagerInit},
-	theme_manager::{self, ThemeManager},
+	schedule::{self, ScheduledTheme, Transition},
+	theme::{
+		accent,
+		export::{self, ExportFormat},
+		extract,
+		generate::{self, ThemeSeeds},
+		import::{self, ImportFormat},
+		lint::{self, Issue}
+	},
+	theme_manager::{self, ThemeFilter, ThemeLocation, ThemeManager},
 	utils::xdg::{self, XdgDirs}
 };
 
@@ -17,15 +34,20 @@ pub enum Error {
 	Xdg(#[from] xdg::Error),
 	Files(#[from] files::Error),
 	Config(#[from] config::Error),
+	ConfigManager(#[from] config_manager::Error),
 	FileManager(#[from] file_manager::Error),
 	ThemeManager(#[from] theme_manager::Error),
-	ModuleManager(#[from] module_manager::Error)
+	Extract(#[from] extract::Error),
+	Import(#[from] import::Error),
+	ModuleManager(#[from] module_manager::Error),
+	Schedule(#[from] schedule::Error)
 }
 
 pub struct NijiApp {
 	_xdg: Rc<XdgDirs>,
-	_files: Rc<Files>,
+	files: Rc<Files>,
 	config: Rc<Config>,
+	config_manager: Rc<ConfigManager>,
 	_file_manager: Rc<FileManager>,
 	theme_manager: Rc<ThemeManager>,
 	module_manager: Rc<ModuleManager>
@@ -35,7 +57,8 @@ impl NijiApp {
 	pub fn init() -> Result<Self, Error> {
 		let xdg = Rc::new(XdgDirs::new()?);
 		let files = Rc::new(Files::new(&xdg)?);
-		let config = Rc::<Config>::new(config::read(files.config_file())?);
+		let config = Rc::new(Config::read(files.config_file())?);
+		let config_manager = Rc::new(ConfigManager::new(Rc::clone(&files)));
 		let file_manager = Rc::new(FileManager::new(Rc::clone(&files))?);
 		let theme_manager = Rc::new(ThemeManager::new(Rc::clone(&files)));
 		let module_manager = Rc::new(ModuleManager::new(ModuleManagerInit {
@@ -47,14 +70,54 @@ impl NijiApp {
 
 		Ok(Self {
 			_xdg: xdg,
-			_files: files,
+			files,
 			config,
+			config_manager,
 			_file_manager: file_manager,
 			theme_manager,
 			module_manager
 		})
 	}
 
+	/// Sets up only what is needed to read and edit the config file. Unlike `init`, this
+	/// doesn't require the config to exist or be valid.
+	pub fn init_config_manager() -> Result<ConfigManager, Error> {
+		let xdg = XdgDirs::new()?;
+		let files = Rc::new(Files::new(&xdg)?);
+		Ok(ConfigManager::new(files))
+	}
+
+	/// The path of the socket the daemon listens on, if `$XDG_RUNTIME_DIR` is set. Like
+	/// `init_config_manager`, this doesn't require the config to exist or be valid.
+	pub fn socket_file() -> Result<Option<PathBuf>, Error> {
+		let xdg = XdgDirs::new()?;
+		let files = Files::new(&xdg)?;
+		Ok(files.socket_file().map(Path::to_path_buf))
+	}
+
+	pub fn config_file(&self) -> &Path {
+		self.files.config_file()
+	}
+
+	/// The names of the modules activated in the config
+	pub fn active_modules(&self) -> &[String] {
+		&self.config.modules
+	}
+
+	/// The names and directories of the active modules
+	pub fn active_module_dirs(&self) -> Vec<(String, PathBuf)> {
+		self.module_manager.active_module_dirs()
+	}
+
+	pub fn themes_dirs(&self) -> &[PathBuf] {
+		self.files.themes_dirs()
+	}
+
+	/// The config values `module` is applied with
+	pub fn module_config(&self, module: &str) -> ModuleConfig {
+		self.config.resolve_module_config(module)
+	}
+
 	pub fn current_theme(&self) -> Result<Theme, Error> {
 		Ok(self.theme_manager.current_theme()?)
 	}
@@ -63,23 +126,226 @@ impl NijiApp {
 		Ok(self.theme_manager.get_theme(name)?)
 	}
 
-	pub fn list_themes(&self) -> Vec<String> {
-		self.theme_manager.list_themes()
+	pub fn find_themes(&self) -> Vec<ThemeLocation> {
+		self.theme_manager.find_themes()
 	}
 
-	pub fn apply(&self, reload: bool, modules: Option<&[String]>) -> Result<(), Error> {
-		let theme = self.current_theme()?;
+	/// Applies the current theme to the active modules, or to `modules` if set. Modules that
+	/// were already applied with the same theme and config are skipped, unless `force` is set.
+	pub fn apply(
+		&self,
+		reload: bool,
+		force: bool,
+		modules: Option<&[String]>
+	) -> Result<(), Error> {
+		let theme = self.with_accent(self.current_theme()?);
+
+		// Pinned themes are only loaded for the modules that are applied
+		let pinned_theme = |module: &str| {
+			self.config
+				.module_themes
+				.contains_key(module)
+				.then(|| self.module_theme(module))
+		};
+
 		self.module_manager
-			.apply(&self.config, &theme, reload, modules)?;
+			.apply(&self.config, &theme, pinned_theme, reload, force, modules)?;
 		Ok(())
 	}
 
+	/// The themes pinned to modules in the config, by module name
+	pub fn module_themes(&self) -> &BTreeMap<String, String> {
+		&self.config.module_themes
+	}
+
+	/// The theme `module` is applied with. This is the theme pinned to it in the config, or the
+	/// current theme if it has none, with the configured accent color.
+	pub fn module_theme(&self, module: &str) -> Result<Theme, Error> {
+		let theme = match self.config.module_themes.get(module) {
+			Some(name) => {
+				let name = self.theme_manager.resolve_theme_name(name, None)?;
+				self.get_theme(&name)?
+			}
+			None => self.current_theme()?
+		};
+		Ok(self.with_accent(theme))
+	}
+
+	/// Applies the accent color from the config to `theme`, if one is set
+	fn with_accent(&self, theme: Theme) -> Theme {
+		match &self.config.accent {
+			Some(accent_config) => accent::apply(&theme, accent_config),
+			None => theme
+		}
+	}
+
+	pub fn generate_theme(&self, name: &str, seeds: &ThemeSeeds) -> Result<PathBuf, Error> {
+		let theme = generate::generate(seeds);
+		Ok(self.theme_manager.save_theme(name, &theme)?)
+	}
+
+	/// Blends the themes `a` and `b` in the OKLCH color space, where `ratio` is how much of `b`
+	/// the result contains, from 0 to 1
+	pub fn mix_themes(&self, a: &str, b: &str, ratio: f32) -> Result<Theme, Error> {
+		let mut theme = Theme::blend(&self.get_theme(a)?, &self.get_theme(b)?, ratio);
+		theme.meta.description = Some(format!(
+			"A mix of \"{a}\" with {:.0}% of \"{b}\"",
+			ratio * 100.0
+		));
+		Ok(theme)
+	}
+
+	/// Saves `theme` as a custom theme, and returns the path of the created theme file
+	pub fn save_theme(&self, name: &str, theme: &Theme) -> Result<PathBuf, Error> {
+		Ok(self.theme_manager.save_theme(name, theme)?)
+	}
+
+	/// Generates a theme from the colors of an image. If `set_wallpaper` is true, the image is
+	/// also configured as the wallpaper of the new theme.
+	pub fn theme_from_image(
+		&self,
+		name: &str,
+		image: &Path,
+		color_scheme: Option<ColorScheme>,
+		set_wallpaper: bool
+	) -> Result<PathBuf, Error> {
+		let seeds = extract::extract_seeds(image, color_scheme)?;
+		let path = self.generate_theme(name, &seeds)?;
+
+		if set_wallpaper {
+			self.config_manager.set_theme_wallpaper(name, image)?;
+		}
+
+		Ok(path)
+	}
+
+	/// Imports a color scheme from another format as a custom theme. If `name` isn't set, the
+	/// theme is named after the scheme, or the file if the scheme has no name. Returns the
+	/// name of the theme and the path it was saved to.
+	pub fn import_theme(
+		&self,
+		format: ImportFormat,
+		path: &Path,
+		name: Option<&str>
+	) -> Result<(String, PathBuf), Error> {
+		let imported = import::import(format, path)?;
+
+		let name = match (name, imported.name) {
+			(Some(name), _) => name.to_string(),
+			(None, Some(scheme_name)) => import::theme_name(&scheme_name),
+			(None, None) => {
+				import::theme_name(&path.file_stem().unwrap_or_default().to_string_lossy())
+			}
+		};
+
+		let path = self.theme_manager.save_theme(&name, &imported.theme)?;
+		Ok((name, path))
+	}
+
+	pub fn export_theme(&self, name: &str, format: ExportFormat) -> Result<String, Error> {
+		let theme = self.get_theme(name)?;
+		Ok(export::export(&theme, name, format))
+	}
+
+	pub fn lint_theme(&self, theme: &Theme) -> Vec<Issue> {
+		lint::lint(theme)
+	}
+
 	pub fn unset_theme(&self) -> Result<(), Error> {
 		Ok(self.theme_manager.unset_theme()?)
 	}
 
-	pub fn set_theme(&self, name: &str) -> Result<(), Error> {
-		self.theme_manager.set_theme(name.to_string())?;
-		Ok(())
+	/// Sets the current theme, and returns its name. See `ThemeManager::resolve_theme_name`
+	/// for how `name` and `color_scheme` are resolved to a theme.
+	pub fn set_theme(
+		&self,
+		name: &str,
+		color_scheme: Option<ColorScheme>
+	) -> Result<String, Error> {
+		let name = self.theme_manager.resolve_theme_name(name, color_scheme)?;
+		self.theme_manager.set_theme(name.clone())?;
+		Ok(name)
+	}
+
+	pub fn toggle_scheme(&self) -> Result<String, Error> {
+		Ok(self.theme_manager.toggle_scheme()?)
+	}
+
+	/// Switches to the theme `name`, fading the modules that reload fast from the current
+	/// theme to the new one over `duration` in `steps` steps, and then applies the new theme
+	/// to all modules. Returns the name of the theme, which may be a variant of the family
+	/// `name`.
+	pub fn transition_theme(
+		&self,
+		name: &str,
+		color_scheme: Option<ColorScheme>,
+		duration: Duration,
+		steps: u32
+	) -> Result<String, Error> {
+		let name = self.theme_manager.resolve_theme_name(name, color_scheme)?;
+		let target = self.with_accent(self.get_theme(&name)?);
+
+		match self.current_theme() {
+			Ok(current) => self.module_manager.transition(
+				&self.config,
+				&self.with_accent(current),
+				&target,
+				duration,
+				steps
+			),
+			Err(err) => debug!("Not transitioning from the current theme: {err}")
+		}
+
+		self.theme_manager.set_theme(name.clone())?;
+		self.apply(true, false, None)?;
+		Ok(name)
+	}
+
+	/// Goes back to the theme that was set before the current one
+	pub fn previous_theme(&self) -> Result<String, Error> {
+		Ok(self.theme_manager.previous_theme()?)
+	}
+
+	/// Switches to the next or previous theme in the sorted list of themes matching `filter`
+	pub fn cycle_theme(&self, filter: &ThemeFilter, forward: bool) -> Result<String, Error> {
+		Ok(self.theme_manager.cycle_theme(filter, forward)?)
+	}
+
+	/// Switches to a random theme matching `filter` that wasn't used recently
+	pub fn random_theme(&self, filter: &ThemeFilter) -> Result<String, Error> {
+		Ok(self.theme_manager.random_theme(filter)?)
+	}
+
+	/// Finds the theme the configured schedule selects at `now`, and when the schedule
+	/// switches themes next
+	pub fn scheduled_theme(&self, now: &DateTime<Local>) -> Result<ScheduledTheme, Error> {
+		let schedule = self.schedule()?;
+		let state = schedule::state_at(schedule, now)?;
+
+		let name = match state.color_scheme {
+			ColorScheme::Light => &schedule.light,
+			ColorScheme::Dark => &schedule.dark
+		};
+		let name = self
+			.theme_manager
+			.resolve_theme_name(name, Some(state.color_scheme.clone()))?;
+
+		Ok(ScheduledTheme {
+			name,
+			color_scheme: state.color_scheme,
+			next_transition: state.next_transition
+		})
+	}
+
+	pub fn schedule_transitions(&self, date: NaiveDate) -> Result<Vec<Transition<Local>>, Error> {
+		Ok(schedule::transitions(self.schedule()?, date, &Local)?)
+	}
+
+	fn schedule(&self) -> Result<&Schedule, Error> {
+		Ok(self
+			.config
+			.schedule
+			.as_ref()
+			.ok_or(schedule::Error::NotConfigured)?)
 	}
 }
@@ -1,8 +1,21 @@
-use clap::{Arg, ArgAction, ArgMatches, Command};
-use log::{error, LevelFilter};
+use std::{fs, path::PathBuf, thread, time::Duration};
+
+use chrono::{DateTime, Local};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use log::{error, info, warn, LevelFilter};
 use niji_console::ColorChoice;
 
-use crate::app::NijiApp;
+use crate::{
+	app::NijiApp,
+	config::{ColorScheme, Theme},
+	config_manager::ConfigManager,
+	daemon::{self, Client, Request, Response, Status, ThemeSelection},
+	schema,
+	theme::{export::ExportFormat, generate::ThemeSeeds, import::ImportFormat, lint::Severity},
+	theme_manager::{ThemeFilter, ThemeLocation},
+	types::color::Color,
+	watch
+};
 
 const AUTHOR: &str = "Nicholas Roether <nicholas.roether@t-online.de>";
 
@@ -58,6 +71,13 @@ pub fn run() {
 				.global(true)
 				.help("Disable color output")
 		)
+		.arg(
+			Arg::new("no_daemon")
+				.long("no-daemon")
+				.action(ArgAction::SetTrue)
+				.global(true)
+				.help("Run the command in this process, even if the daemon is running")
+		)
 		.subcommand(
 			Command::new("apply")
 				.about("Apply (or re-apply) the current theme and and configuration")
@@ -82,6 +102,71 @@ pub fn run() {
 							 Changes will only take effect after a restart."
 						)
 				)
+				.arg(
+					Arg::new("force")
+						.long("force")
+						.short('f')
+						.action(ArgAction::SetTrue)
+						.help(
+							"Apply all modules, including the ones that haven't changed since \
+							 they were last applied"
+						)
+				)
+		)
+		.subcommand(
+			Command::new("watch")
+				.about(
+					"Watch the config, the themes and the active modules, and apply the affected \
+					 modules whenever they change"
+				)
+				.arg(
+					Arg::new("no_reload")
+						.long("no-reload")
+						.short('k')
+						.action(ArgAction::SetTrue)
+						.help("Don't reload the affected modules")
+				)
+		)
+		.subcommand(
+			Command::new("status").about(
+				"Show the current theme, the active modules and whether the daemon is running"
+			)
+		)
+		.subcommand(
+			Command::new("daemon")
+				.about(
+					"Keep niji loaded in the background, so that commands like `niji theme set` \
+					 take effect instantly"
+				)
+				.subcommand_required(true)
+				.subcommand(Command::new("run").about("Run the daemon in the foreground"))
+				.subcommand(Command::new("stop").about("Stop the running daemon"))
+		)
+		.subcommand(
+			Command::new("schedule")
+				.about("Switch between a light and a dark theme automatically")
+				.subcommand_required(true)
+				.subcommand(
+					Command::new("run")
+						.about(
+							"Apply the theme the schedule selects right now, and then keep \
+							 switching themes whenever the schedule does"
+						)
+						.arg(
+							Arg::new("no_reload")
+								.long("no-reload")
+								.short('k')
+								.action(ArgAction::SetTrue)
+								.help(
+									"Do not reload the module targets to apply the changes \
+									 immediately. Changes will only take effect after a restart."
+								)
+						)
+				)
+				.subcommand(Command::new("show").about(
+					"Show when the schedule switches themes today, and which theme it selects \
+					 right now"
+				))
 		)
 		.subcommand(
 			Command::new("theme")
@@ -102,7 +187,17 @@ pub fn run() {
 					Command::new("set")
 						.about("Change the current theme")
 						.arg_required_else_help(true)
-						.arg(Arg::new("name").help("The name of the theme to change to"))
+						.arg(Arg::new("name").help(
+							"The name of the theme to change to. May also be the name of a theme \
+							 family like \"catppuccin\", in which case the variant with the color \
+							 scheme of the current theme is used."
+						))
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help("Use the light or dark variant of the theme family")
+						)
 						.arg(
 							Arg::new("no_apply")
 								.long("no-apply")
@@ -122,12 +217,476 @@ pub fn run() {
 								)
 						)
 				)
-				.subcommand(Command::new("list").about("List the names of available themes"))
+				.subcommand(
+					Command::new("toggle-scheme")
+						.about(
+							"Switch between the light and dark variants of the current theme \
+							 family"
+						)
+						.arg(
+							Arg::new("no_apply")
+								.long("no-apply")
+								.short('n')
+								.action(ArgAction::SetTrue)
+								.help("Don't apply the theme after switching to it")
+								.conflicts_with("no_reload")
+						)
+						.arg(
+							Arg::new("no_reload")
+								.long("no-reload")
+								.short('k')
+								.action(ArgAction::SetTrue)
+								.help(
+									"Do not reload the module targets to apply the changes \
+									 immediately. Changes will only take effect after a restart."
+								)
+						)
+				)
+				.subcommand(
+					Command::new("previous")
+						.about("Go back to the theme that was set before the current one")
+						.arg(
+							Arg::new("no_apply")
+								.long("no-apply")
+								.short('n')
+								.action(ArgAction::SetTrue)
+								.help("Don't apply the theme after switching to it")
+								.conflicts_with("no_reload")
+						)
+						.arg(
+							Arg::new("no_reload")
+								.long("no-reload")
+								.short('k')
+								.action(ArgAction::SetTrue)
+								.help(
+									"Do not reload the module targets to apply the changes \
+									 immediately. Changes will only take effect after a restart."
+								)
+						)
+				)
+				.subcommand(
+					Command::new("next")
+						.about("Switch to the next theme in alphabetical order")
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help("Only choose from light or dark themes")
+						)
+						.arg(Arg::new("tag").long("tag").action(ArgAction::Append).help(
+							"Only choose from themes with this tag. Can be set multiple times to \
+							 only choose from themes with all of the tags."
+						))
+						.arg(
+							Arg::new("no_apply")
+								.long("no-apply")
+								.short('n')
+								.action(ArgAction::SetTrue)
+								.help("Don't apply the theme after switching to it")
+								.conflicts_with("no_reload")
+						)
+						.arg(
+							Arg::new("no_reload")
+								.long("no-reload")
+								.short('k')
+								.action(ArgAction::SetTrue)
+								.help(
+									"Do not reload the module targets to apply the changes \
+									 immediately. Changes will only take effect after a restart."
+								)
+						)
+				)
+				.subcommand(
+					Command::new("prev")
+						.about("Switch to the previous theme in alphabetical order")
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help("Only choose from light or dark themes")
+						)
+						.arg(Arg::new("tag").long("tag").action(ArgAction::Append).help(
+							"Only choose from themes with this tag. Can be set multiple times to \
+							 only choose from themes with all of the tags."
+						))
+						.arg(
+							Arg::new("no_apply")
+								.long("no-apply")
+								.short('n')
+								.action(ArgAction::SetTrue)
+								.help("Don't apply the theme after switching to it")
+								.conflicts_with("no_reload")
+						)
+						.arg(
+							Arg::new("no_reload")
+								.long("no-reload")
+								.short('k')
+								.action(ArgAction::SetTrue)
+								.help(
+									"Do not reload the module targets to apply the changes \
+									 immediately. Changes will only take effect after a restart."
+								)
+						)
+				)
+				.subcommand(
+					Command::new("random")
+						.about("Switch to a random theme that wasn't used recently")
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help("Only choose from light or dark themes")
+						)
+						.arg(Arg::new("tag").long("tag").action(ArgAction::Append).help(
+							"Only choose from themes with this tag. Can be set multiple times to \
+							 only choose from themes with all of the tags."
+						))
+						.arg(
+							Arg::new("no_apply")
+								.long("no-apply")
+								.short('n')
+								.action(ArgAction::SetTrue)
+								.help("Don't apply the theme after switching to it")
+								.conflicts_with("no_reload")
+						)
+						.arg(
+							Arg::new("no_reload")
+								.long("no-reload")
+								.short('k')
+								.action(ArgAction::SetTrue)
+								.help(
+									"Do not reload the module targets to apply the changes \
+									 immediately. Changes will only take effect after a restart."
+								)
+						)
+				)
+				.subcommand(
+					Command::new("list")
+						.about("List the names of available themes")
+						.arg(
+							Arg::new("long")
+								.long("long")
+								.short('l')
+								.action(ArgAction::SetTrue)
+								.help(
+									"Also show the color scheme, metadata and location of each \
+									 theme"
+								)
+						)
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help("Only list light or dark themes")
+						)
+						.arg(Arg::new("tag").long("tag").action(ArgAction::Append).help(
+							"Only list themes with this tag. Can be set multiple times to only \
+							 list themes with all of the tags."
+						))
+				)
+				.subcommand(
+					Command::new("mix")
+						.about("Blend two themes into a new one")
+						.arg_required_else_help(true)
+						.arg(
+							Arg::new("a")
+								.required(true)
+								.help("The first theme to blend")
+						)
+						.arg(
+							Arg::new("b")
+								.required(true)
+								.help("The second theme to blend")
+						)
+						.arg(
+							Arg::new("ratio")
+								.long("ratio")
+								.short('r')
+								.default_value("0.5")
+								.value_parser(parse_ratio)
+								.help(
+									"How much of the second theme the result contains, from 0 to 1"
+								)
+						)
+						.arg(Arg::new("save").long("save").short('s').help(
+							"Save the result as a custom theme with this name instead of \
+							 previewing it"
+						))
+				)
+				.subcommand(
+					Command::new("transition")
+						.about("Gradually fade to another theme")
+						.arg_required_else_help(true)
+						.arg(Arg::new("name").required(true).help(
+							"The name of the theme to change to. May also be the name of a theme \
+							 family, like with `niji theme set`."
+						))
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help("Use the light or dark variant of the theme family")
+						)
+						.arg(
+							Arg::new("duration")
+								.long("duration")
+								.short('d')
+								.default_value("2s")
+								.value_parser(parse_duration)
+								.help(
+									"How long the transition takes, like \"3s\", \"500ms\" or \
+									 \"1m\""
+								)
+						)
+						.arg(
+							Arg::new("steps")
+								.long("steps")
+								.default_value("20")
+								.value_parser(value_parser!(u32).range(1..))
+								.help("How many steps the transition is made of")
+						)
+				)
+				.subcommand(
+					Command::new("generate")
+						.about("Generate a new custom theme from a few seed colors")
+						.arg_required_else_help(true)
+						.arg(
+							Arg::new("name")
+								.required(true)
+								.help("The name of the theme to create")
+						)
+						.arg(
+							Arg::new("background")
+								.long("background")
+								.required(true)
+								.value_parser(value_parser!(Color))
+								.help("The background color of the theme")
+						)
+						.arg(
+							Arg::new("primary")
+								.long("primary")
+								.required(true)
+								.value_parser(value_parser!(Color))
+								.help("The primary accent color of the theme")
+						)
+						.arg(
+							Arg::new("secondary")
+								.long("secondary")
+								.value_parser(value_parser!(Color))
+								.help(
+									"The secondary accent color of the theme. Derived from the \
+									 primary color if not set."
+								)
+						)
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help(
+									"Whether to generate a light or dark theme. Guessed from the \
+									 background color if not set."
+								)
+						)
+				)
+				.subcommand(
+					Command::new("from-image")
+						.about("Generate a new custom theme from the colors of an image")
+						.arg_required_else_help(true)
+						.arg(
+							Arg::new("path")
+								.required(true)
+								.value_parser(value_parser!(PathBuf))
+								.help("The PNG or JPEG image to take the colors from")
+						)
+						.arg(Arg::new("name").long("name").short('N').help(
+							"The name of the theme to create. Defaults to the file name of the \
+							 image."
+						))
+						.arg(
+							Arg::new("scheme")
+								.long("scheme")
+								.value_parser(["light", "dark"])
+								.help(
+									"Whether to generate a light or dark theme. Guessed from the \
+									 average lightness of the image if not set."
+								)
+						)
+						.arg(
+							Arg::new("wallpaper")
+								.long("wallpaper")
+								.short('w')
+								.action(ArgAction::SetTrue)
+								.help("Also use the image as the wallpaper for the new theme")
+						)
+				)
+				.subcommand(
+					Command::new("import")
+						.about("Import a color scheme from another format as a custom theme")
+						.arg_required_else_help(true)
+						.arg(
+							Arg::new("format")
+								.required(true)
+								.value_parser([
+									"base16",
+									"base24",
+									"kitty",
+									"alacritty",
+									"foot",
+									"iterm2",
+									"xresources"
+								])
+								.help("The format of the color scheme")
+						)
+						.arg(
+							Arg::new("path")
+								.required(true)
+								.value_parser(value_parser!(PathBuf))
+								.help("The file to import")
+						)
+						.arg(Arg::new("name").long("name").short('N').help(
+							"The name of the theme to create. Defaults to the name of the color \
+							 scheme."
+						))
+				)
+				.subcommand(
+					Command::new("export")
+						.about(
+							"Render a theme in the format of another program, without applying it"
+						)
+						.arg_required_else_help(true)
+						.arg(
+							Arg::new("name")
+								.required(true)
+								.help("The name of the theme to export")
+						)
+						.arg(
+							Arg::new("format")
+								.long("format")
+								.short('f')
+								.required(true)
+								.value_parser([
+									"base16",
+									"xresources",
+									"kitty",
+									"alacritty",
+									"foot",
+									"gimp",
+									"css",
+									"scss",
+									"json"
+								])
+								.help("The format to export the theme to")
+						)
+						.arg(
+							Arg::new("output")
+								.long("output")
+								.short('o')
+								.value_parser(value_parser!(PathBuf))
+								.help("The file to write to. Prints to stdout if not set.")
+						)
+				)
+				.subcommand(
+					Command::new("lint")
+						.about(
+							"Check a theme for text that is hard to read and colors that are hard \
+							 to tell apart"
+						)
+						.arg(
+							Arg::new("name").help(
+								"The theme to check. Defaults to the current theme if not set."
+							)
+						)
+				)
 				.subcommand(Command::new("unset").about(
 					"Unset the current theme. Note that this will not make any changes to the \
 					 emitted files!"
 				))
 		)
+		.subcommand(
+			Command::new("config")
+				.about("Read and edit the config file")
+				.subcommand_required(true)
+				.subcommand(
+					Command::new("get")
+						.about("Print the value of a config option")
+						.arg_required_else_help(true)
+						.arg(Arg::new("key").help(
+							"The dotted key of the option, for example \"global.font_family\""
+						))
+				)
+				.subcommand(
+					Command::new("set")
+						.about(
+							"Set the value of a config option, keeping the comments and \
+							 formatting of the config file intact"
+						)
+						.arg_required_else_help(true)
+						.arg(Arg::new("key").required(true).help(
+							"The dotted key of the option, for example \"waybar.font_scale\""
+						))
+						.arg(Arg::new("value").required(true).help(
+							"The new value. Valid TOML values like `1.2`, `true` or `[\"a\", \
+							 \"b\"]` are used as-is; anything else is stored as a string."
+						))
+				)
+				.subcommand(
+					Command::new("modules")
+						.about("Activate or deactivate modules")
+						.subcommand_required(true)
+						.subcommand(
+							Command::new("add")
+								.about("Add modules to the list of active modules")
+								.arg_required_else_help(true)
+								.arg(
+									Arg::new("names")
+										.action(ArgAction::Append)
+										.help("The names of the modules to activate")
+								)
+						)
+						.subcommand(
+							Command::new("remove")
+								.about("Remove modules from the list of active modules")
+								.arg_required_else_help(true)
+								.arg(
+									Arg::new("names")
+										.action(ArgAction::Append)
+										.help("The names of the modules to deactivate")
+								)
+						)
+				)
+				.subcommand(
+					Command::new("show")
+						.about("Print the config file, or the config of a single module")
+						.arg(Arg::new("module").help(
+							"The module whose config section to print. Prints the whole config \
+							 file if not set."
+						))
+						.arg(
+							Arg::new("resolved")
+								.long("resolved")
+								.short('r')
+								.action(ArgAction::SetTrue)
+								.requires("module")
+								.help(
+									"Print the effective config the module receives, after \
+									 merging the global options with the module-specific ones"
+								)
+						)
+				)
+		)
+		.subcommand(
+			Command::new("schema")
+				.about(
+					"Print JSON schemas for niji's files, for validation and autocompletion in \
+					 editors"
+				)
+				.subcommand_required(true)
+				.subcommand(Command::new("config").about(
+					"Print the JSON schema for config.toml, including the options of all \
+					 installed modules"
+				))
+				.subcommand(Command::new("theme").about("Print the JSON schema for theme files"))
+		)
 		.get_matches();
 
 	cmd(&matches)
@@ -154,30 +713,180 @@ fn cmd(args: &ArgMatches) {
 
 	niji_console::init(level, color_choice);
 
+	// The config and schema commands need to work even if the config is missing or
+	// invalid, so they don't go through the full app initialization.
+	match args.subcommand() {
+		Some(("config", args)) => {
+			let config_manager = handle!(NijiApp::init_config_manager());
+			cmd_config(&config_manager, args);
+			return;
+		}
+		Some(("schema", args)) => {
+			let config_manager = handle!(NijiApp::init_config_manager());
+			cmd_schema(&config_manager, args);
+			return;
+		}
+		_ => ()
+	}
+
+	match args.subcommand() {
+		Some(("daemon", args)) => {
+			cmd_daemon(args);
+			return;
+		}
+		Some(("watch", args)) => {
+			cmd_watch(args);
+			return;
+		}
+		_ => ()
+	}
+
+	// Commands that the daemon can run are sent to it if it is running, which saves loading
+	// the config and the modules again
+	if let Some(request) = daemon_request(args) {
+		let no_daemon = *args.get_one::<bool>("no_daemon").unwrap();
+		let client = if no_daemon {
+			None
+		} else {
+			handle!(NijiApp::socket_file())
+				.as_deref()
+				.and_then(Client::connect)
+		};
+
+		let response = match client {
+			Some(client) => handle!(client.request(&request)),
+			None => daemon::handle_request(&handle!(NijiApp::init()), request)
+		};
+		report_response(args, response);
+		return;
+	}
+
 	let app = handle!(NijiApp::init());
 
 	match args.subcommand() {
-		Some(("apply", args)) => cmd_apply(&app, args),
 		Some(("theme", args)) => cmd_theme(&app, args),
+		Some(("schedule", args)) => cmd_schedule(&app, args),
 		_ => unreachable!()
 	}
 }
 
-fn cmd_apply(app: &NijiApp, args: &ArgMatches) {
-	let no_reload = args.get_one::<bool>("no_reload").unwrap();
-	let modules: Option<Vec<String>> = args
-		.get_many::<String>("modules")
-		.map(|v| v.cloned().collect());
+/// The request to send to the daemon for the given command, if the daemon can run it
+fn daemon_request(args: &ArgMatches) -> Option<Request> {
+	let reload = |args: &ArgMatches| !*args.get_one::<bool>("no_reload").unwrap();
+	let apply = |args: &ArgMatches| !*args.get_one::<bool>("no_apply").unwrap();
 
-	handle!(app.apply(!no_reload, modules.as_deref()))
+	let request = match args.subcommand()? {
+		("apply", args) => Request::Apply {
+			reload: reload(args),
+			force: *args.get_one::<bool>("force").unwrap(),
+			modules: args
+				.get_many::<String>("modules")
+				.map(|v| v.cloned().collect())
+		},
+		("status", _) => Request::Status,
+		("theme", args) => match args.subcommand()? {
+			("set", args) => Request::SetTheme {
+				name: args.get_one::<String>("name").unwrap().clone(),
+				color_scheme: get_color_scheme(args),
+				apply: apply(args),
+				reload: reload(args)
+			},
+			("toggle-scheme", args) => Request::ToggleScheme {
+				apply: apply(args),
+				reload: reload(args)
+			},
+			(command @ ("previous" | "next" | "prev" | "random"), args) => Request::SelectTheme {
+				selection: match command {
+					"previous" => ThemeSelection::Previous,
+					"next" => ThemeSelection::Next(get_theme_filter(args)),
+					"prev" => ThemeSelection::Prev(get_theme_filter(args)),
+					_ => ThemeSelection::Random(get_theme_filter(args))
+				},
+				apply: apply(args),
+				reload: reload(args)
+			},
+			_ => return None
+		},
+		_ => return None
+	};
+	Some(request)
+}
+
+fn report_response(args: &ArgMatches, response: Response) {
+	match response {
+		Response::Done => (),
+		Response::Theme(theme) => match args.subcommand() {
+			Some(("theme", args)) if args.subcommand_name() != Some("set") => {
+				info!("Switched to theme \"{theme}\"")
+			}
+			Some(("theme", args)) => {
+				let name = args
+					.subcommand()
+					.and_then(|(_, args)| args.get_one::<String>("name"));
+				if name != Some(&theme) {
+					info!("Using theme \"{theme}\"");
+				}
+			}
+			_ => ()
+		},
+		Response::Status(status) => print_status(&status),
+		Response::Error(message) => error!("{message}")
+	}
+}
+
+fn print_status(status: &Status) {
+	niji_console::println!("Theme: {}", status.theme.as_deref().unwrap_or("(none)"));
+	if status.modules.is_empty() {
+		niji_console::println!("Modules: (none)");
+	} else {
+		let modules: Vec<String> = status
+			.modules
+			.iter()
+			.map(|module| match status.pinned_themes.get(module) {
+				Some(theme) => format!("{module} (pinned to {theme})"),
+				None => module.clone()
+			})
+			.collect();
+		niji_console::println!("Modules: {}", modules.join(", "));
+	}
+	match status.daemon_pid {
+		Some(pid) => niji_console::println!("Daemon: running (pid {pid})"),
+		None => niji_console::println!("Daemon: not running")
+	}
+}
+
+fn cmd_watch(args: &ArgMatches) {
+	let no_reload = *args.get_one::<bool>("no_reload").unwrap();
+	handle!(watch::run(!no_reload))
+}
+
+fn cmd_daemon(args: &ArgMatches) {
+	let socket = handle!(daemon::socket_file());
+
+	match args.subcommand() {
+		Some(("run", _)) => handle!(daemon::run(&socket)),
+		Some(("stop", _)) => {
+			let client = handle!(Client::connect(&socket).ok_or(daemon::Error::NotRunning));
+			if let Response::Error(message) = handle!(client.request(&Request::Stop)) {
+				error!("{message}");
+			}
+		}
+		_ => unreachable!()
+	}
 }
 
 fn cmd_theme(app: &NijiApp, args: &ArgMatches) {
 	match args.subcommand() {
 		Some(("get", _)) => cmd_theme_get(app),
 		Some(("show", args)) => cmd_theme_show(app, args),
-		Some(("set", args)) => cmd_theme_set(app, args),
-		Some(("list", _)) => cmd_theme_list(app),
+		Some(("list", args)) => cmd_theme_list(app, args),
+		Some(("mix", args)) => cmd_theme_mix(app, args),
+		Some(("transition", args)) => cmd_theme_transition(app, args),
+		Some(("generate", args)) => cmd_theme_generate(app, args),
+		Some(("from-image", args)) => cmd_theme_from_image(app, args),
+		Some(("import", args)) => cmd_theme_import(app, args),
+		Some(("export", args)) => cmd_theme_export(app, args),
+		Some(("lint", args)) => cmd_theme_lint(app, args),
 		Some(("unset", _)) => cmd_theme_unset(app),
 		_ => unreachable!()
 	}
@@ -211,30 +920,426 @@ fn cmd_theme_show(app: &NijiApp, args: &ArgMatches) {
 	niji_console::println!("{theme}")
 }
 
-fn cmd_theme_set(app: &NijiApp, args: &ArgMatches) {
-	let name = args.get_one::<String>("name").unwrap().as_str();
-	let no_apply = *args.get_one::<bool>("no_apply").unwrap();
-	let no_reload = *args.get_one::<bool>("no_reload").unwrap();
-
-	handle!(app.set_theme(name));
-	if !no_apply {
-		handle!(app.apply(!no_reload, None));
-	}
-}
+fn cmd_theme_list(app: &NijiApp, args: &ArgMatches) {
+	let long = *args.get_one::<bool>("long").unwrap();
+	let filter = get_theme_filter(args);
 
-fn cmd_theme_list(app: &NijiApp) {
 	let mut empty = true;
 
-	for theme in app.list_themes() {
+	for location in app.find_themes() {
+		// The themes only need to be read if their contents are shown or filtered by
+		let theme = if long || !filter.is_empty() {
+			match app.get_theme(&location.name) {
+				Ok(theme) => Some(theme),
+				Err(err) if !filter.is_empty() => {
+					warn!("Skipping theme \"{}\": {err}", location.name);
+					continue;
+				}
+				Err(err) => {
+					error!("{err}");
+					None
+				}
+			}
+		} else {
+			None
+		};
+
+		if theme.as_ref().is_some_and(|theme| !filter.matches(theme)) {
+			continue;
+		}
+
 		empty = false;
-		niji_console::println!("{theme}")
+		if long {
+			print_theme_long(&location, theme.as_ref());
+		} else {
+			niji_console::println!("{}", location.name)
+		}
 	}
 
-	if empty {
+	if empty && !filter.is_empty() {
+		error!("No themes match the given filters");
+	} else if empty {
 		error!("No usable themes were found");
 	}
 }
 
+fn print_theme_long(location: &ThemeLocation, theme: Option<&Theme>) {
+	let display_name = theme.and_then(|theme| theme.meta.name.as_ref());
+	match display_name {
+		Some(display_name) => niji_console::println!("{} ({display_name})", location.name),
+		None => niji_console::println!("{}", location.name)
+	}
+
+	if let Some(theme) = theme {
+		niji_console::println!("  Color scheme: {}", theme.ui.color_scheme);
+	}
+	niji_console::println!("  Source: {}", location.dir.display());
+	for path in &location.shadows {
+		niji_console::println!("  Shadows: {}", path.display());
+	}
+
+	if let Some(theme) = theme {
+		let meta = &theme.meta;
+		if let Some(author) = &meta.author {
+			niji_console::println!("  Author: {author}");
+		}
+		if let Some(license) = &meta.license {
+			niji_console::println!("  License: {license}");
+		}
+		if !meta.tags.is_empty() {
+			niji_console::println!("  Tags: {}", meta.tags.join(", "));
+		}
+		if let Some(description) = &meta.description {
+			niji_console::println!("  {description}");
+		}
+	}
+}
+
+fn get_color_scheme(args: &ArgMatches) -> Option<ColorScheme> {
+	args.get_one::<String>("scheme")
+		.map(|scheme| match scheme.as_str() {
+			"light" => ColorScheme::Light,
+			_ => ColorScheme::Dark
+		})
+}
+
+fn cmd_theme_mix(app: &NijiApp, args: &ArgMatches) {
+	let a = args.get_one::<String>("a").unwrap();
+	let b = args.get_one::<String>("b").unwrap();
+	let ratio = *args.get_one::<f32>("ratio").unwrap();
+
+	let theme = handle!(app.mix_themes(a, b, ratio));
+
+	match args.get_one::<String>("save") {
+		Some(name) => {
+			let path = handle!(app.save_theme(name, &theme));
+			info!("Saved theme \"{name}\" at {}", path.display());
+		}
+		None => {
+			niji_console::println!("Mix of \"{a}\" and \"{b}\" at {ratio}:");
+			niji_console::println!();
+			niji_console::println!("{theme}")
+		}
+	}
+}
+
+fn cmd_theme_transition(app: &NijiApp, args: &ArgMatches) {
+	let name = args.get_one::<String>("name").unwrap();
+	let duration = *args.get_one::<Duration>("duration").unwrap();
+	let steps = *args.get_one::<u32>("steps").unwrap();
+
+	let theme = handle!(app.transition_theme(name, get_color_scheme(args), duration, steps));
+	if theme != *name {
+		info!("Using theme \"{theme}\"");
+	}
+}
+
+fn parse_ratio(value: &str) -> Result<f32, String> {
+	let ratio: f32 = value
+		.parse()
+		.map_err(|_| format!("\"{value}\" is not a number"))?;
+	if !(0.0..=1.0).contains(&ratio) {
+		return Err("The ratio must be between 0 and 1".to_string());
+	}
+	Ok(ratio)
+}
+
+/// Parses durations like "3s", "500ms" or "1m". Numbers without a unit are seconds.
+fn parse_duration(value: &str) -> Result<Duration, String> {
+	let (number, seconds_per_unit) = if let Some(number) = value.strip_suffix("ms") {
+		(number, 0.001)
+	} else if let Some(number) = value.strip_suffix('s') {
+		(number, 1.0)
+	} else if let Some(number) = value.strip_suffix('m') {
+		(number, 60.0)
+	} else {
+		(value, 1.0)
+	};
+
+	let number: f64 = number
+		.trim()
+		.parse()
+		.map_err(|_| format!("\"{value}\" is not a duration"))?;
+	Duration::try_from_secs_f64(number * seconds_per_unit)
+		.map_err(|_| format!("\"{value}\" is not a valid duration"))
+}
+
+fn get_theme_filter(args: &ArgMatches) -> ThemeFilter {
+	ThemeFilter {
+		color_scheme: get_color_scheme(args),
+		tags: args
+			.get_many::<String>("tag")
+			.unwrap_or_default()
+			.cloned()
+			.collect()
+	}
+}
+
+fn cmd_theme_generate(app: &NijiApp, args: &ArgMatches) {
+	let name = args.get_one::<String>("name").unwrap();
+	let color_scheme = get_color_scheme(args);
+
+	let seeds = ThemeSeeds {
+		background: *args.get_one::<Color>("background").unwrap(),
+		primary: *args.get_one::<Color>("primary").unwrap(),
+		secondary: args.get_one::<Color>("secondary").copied(),
+		color_scheme,
+		palette: Vec::new()
+	};
+
+	let path = handle!(app.generate_theme(name, &seeds));
+	info!("Generated theme \"{name}\" at {}", path.display());
+}
+
+fn cmd_theme_from_image(app: &NijiApp, args: &ArgMatches) {
+	let image = args.get_one::<PathBuf>("path").unwrap();
+	let set_wallpaper = *args.get_one::<bool>("wallpaper").unwrap();
+
+	let name = match args.get_one::<String>("name") {
+		Some(name) => name.clone(),
+		None => image
+			.file_stem()
+			.unwrap_or_default()
+			.to_string_lossy()
+			.to_string()
+	};
+
+	let image = handle!(image
+		.canonicalize()
+		.map_err(|e| format!("Failed to access {}: {e}", image.display())));
+
+	let path = handle!(app.theme_from_image(&name, &image, get_color_scheme(args), set_wallpaper));
+	info!("Generated theme \"{name}\" at {}", path.display());
+}
+
+fn cmd_theme_import(app: &NijiApp, args: &ArgMatches) {
+	let path = args.get_one::<PathBuf>("path").unwrap();
+	let name = args.get_one::<String>("name");
+
+	let format = match args.get_one::<String>("format").unwrap().as_str() {
+		"base16" | "base24" => ImportFormat::Base16,
+		"kitty" => ImportFormat::Kitty,
+		"alacritty" => ImportFormat::Alacritty,
+		"foot" => ImportFormat::Foot,
+		"iterm2" => ImportFormat::Iterm2,
+		"xresources" => ImportFormat::Xresources,
+		_ => unreachable!()
+	};
+
+	let (name, path) = handle!(app.import_theme(format, path, name.map(String::as_str)));
+	info!("Imported theme \"{name}\" to {}", path.display());
+}
+
+fn cmd_theme_export(app: &NijiApp, args: &ArgMatches) {
+	let name = args.get_one::<String>("name").unwrap();
+	let output = args.get_one::<PathBuf>("output");
+
+	let format = match args.get_one::<String>("format").unwrap().as_str() {
+		"base16" => ExportFormat::Base16,
+		"xresources" => ExportFormat::Xresources,
+		"kitty" => ExportFormat::Kitty,
+		"alacritty" => ExportFormat::Alacritty,
+		"foot" => ExportFormat::Foot,
+		"gimp" => ExportFormat::Gimp,
+		"css" => ExportFormat::Css,
+		"scss" => ExportFormat::Scss,
+		"json" => ExportFormat::Json,
+		_ => unreachable!()
+	};
+
+	let exported = handle!(app.export_theme(name, format));
+
+	match output {
+		Some(path) => {
+			handle!(fs::write(path, exported)
+				.map_err(|e| format!("Failed to write {}: {e}", path.display())));
+			info!("Exported theme \"{name}\" to {}", path.display());
+		}
+		None => niji_console::println!("{}", exported.trim_end())
+	}
+}
+
+fn cmd_theme_lint(app: &NijiApp, args: &ArgMatches) {
+	let theme = match args.get_one::<String>("name") {
+		Some(name) => handle!(app.get_theme(name)),
+		None => handle!(app.current_theme())
+	};
+	let name = theme.name.as_deref().unwrap();
+
+	let issues = app.lint_theme(&theme);
+	if issues.is_empty() {
+		info!("Found no issues in theme \"{name}\"");
+		return;
+	}
+
+	for issue in &issues {
+		niji_console::println!("{issue}");
+	}
+
+	let errors = issues
+		.iter()
+		.filter(|issue| issue.severity == Severity::Error)
+		.count();
+	let warnings = issues.len() - errors;
+	niji_console::println!();
+	niji_console::println!("Theme \"{name}\": {errors} error(s), {warnings} warning(s)");
+}
+
 fn cmd_theme_unset(app: &NijiApp) {
 	handle!(app.unset_theme())
 }
+
+fn cmd_schedule(app: &NijiApp, args: &ArgMatches) {
+	match args.subcommand() {
+		Some(("run", args)) => cmd_schedule_run(app, args),
+		Some(("show", _)) => cmd_schedule_show(app),
+		_ => unreachable!()
+	}
+}
+
+fn cmd_schedule_run(app: &NijiApp, args: &ArgMatches) {
+	let no_reload = *args.get_one::<bool>("no_reload").unwrap();
+
+	loop {
+		let scheduled = handle!(app.scheduled_theme(&Local::now()));
+		let current = app.current_theme().ok().and_then(|theme| theme.name);
+
+		if current.as_ref() != Some(&scheduled.name) {
+			info!(
+				"Switching to the {} theme \"{}\"",
+				scheduled.color_scheme, scheduled.name
+			);
+
+			// Failing to apply one theme shouldn't stop the schedule
+			let result = app
+				.set_theme(&scheduled.name, None)
+				.and_then(|_| app.apply(!no_reload, false, None));
+			if let Err(err) = result {
+				error!("{err}");
+			}
+		}
+
+		let Some(next_transition) = scheduled.next_transition else {
+			info!("The schedule doesn't switch themes within the next year");
+			return;
+		};
+		info!(
+			"Switching themes next at {}",
+			next_transition.format("%Y-%m-%d %H:%M")
+		);
+		sleep_until(next_transition);
+	}
+}
+
+/// Sleeps until `time`. The clock is checked at least once a minute, because sleeping doesn't
+/// take the time the system spends suspended into account.
+fn sleep_until(time: DateTime<Local>) {
+	const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+	while let Ok(remaining) = (time - Local::now()).to_std() {
+		thread::sleep(remaining.min(MAX_SLEEP));
+	}
+}
+
+fn cmd_schedule_show(app: &NijiApp) {
+	let now = Local::now();
+	let scheduled = handle!(app.scheduled_theme(&now));
+	let transitions = handle!(app.schedule_transitions(now.date_naive()));
+
+	niji_console::println!("Today:");
+	for transition in transitions {
+		niji_console::println!(
+			"  {}  {}",
+			transition.time.format("%H:%M"),
+			transition.color_scheme
+		);
+	}
+	niji_console::println!();
+	niji_console::println!(
+		"Right now: {} (\"{}\")",
+		scheduled.color_scheme,
+		scheduled.name
+	);
+	if let Some(next_transition) = scheduled.next_transition {
+		niji_console::println!("Next switch: {}", next_transition.format("%Y-%m-%d %H:%M"));
+	}
+}
+
+fn cmd_config(config_manager: &ConfigManager, args: &ArgMatches) {
+	match args.subcommand() {
+		Some(("get", args)) => cmd_config_get(config_manager, args),
+		Some(("set", args)) => cmd_config_set(config_manager, args),
+		Some(("modules", args)) => cmd_config_modules(config_manager, args),
+		Some(("show", args)) => cmd_config_show(config_manager, args),
+		_ => unreachable!()
+	}
+}
+
+fn cmd_config_get(config_manager: &ConfigManager, args: &ArgMatches) {
+	let key = args.get_one::<String>("key").unwrap();
+
+	let Some(item) = handle!(config_manager.get(key)) else {
+		error!("Config option \"{key}\" is not set");
+		return;
+	};
+
+	match item.as_str() {
+		Some(string) => niji_console::println!("{string}"),
+		None => niji_console::println!("{}", item.to_string().trim())
+	}
+}
+
+fn cmd_config_set(config_manager: &ConfigManager, args: &ArgMatches) {
+	let key = args.get_one::<String>("key").unwrap();
+	let value = args.get_one::<String>("value").unwrap();
+
+	handle!(config_manager.set(key, value))
+}
+
+fn cmd_config_modules(config_manager: &ConfigManager, args: &ArgMatches) {
+	match args.subcommand() {
+		Some(("add", args)) => {
+			let names: Vec<String> = args.get_many::<String>("names").unwrap().cloned().collect();
+			handle!(config_manager.add_modules(&names))
+		}
+		Some(("remove", args)) => {
+			let names: Vec<String> = args.get_many::<String>("names").unwrap().cloned().collect();
+			handle!(config_manager.remove_modules(&names))
+		}
+		_ => unreachable!()
+	}
+}
+
+fn cmd_config_show(config_manager: &ConfigManager, args: &ArgMatches) {
+	let module = args.get_one::<String>("module");
+	let resolved = *args.get_one::<bool>("resolved").unwrap();
+
+	let output = match module {
+		Some(module) if resolved => {
+			let module_config = handle!(config_manager.resolve_module_config(module));
+			handle!(toml::to_string(&handle!(toml::Value::try_from(
+				module_config
+			))))
+		}
+		Some(module) => match handle!(config_manager.get(module)) {
+			Some(item) => item.to_string(),
+			None => {
+				error!("The config has no section for module \"{module}\"");
+				return;
+			}
+		},
+		None => handle!(config_manager.source())
+	};
+
+	niji_console::println!("{}", output.trim_end())
+}
+
+fn cmd_schema(config_manager: &ConfigManager, args: &ArgMatches) {
+	let schema = match args.subcommand() {
+		Some(("config", _)) => config_manager.schema(),
+		Some(("theme", _)) => schema::theme_schema(),
+		_ => unreachable!()
+	};
+
+	niji_console::println!("{}", handle!(serde_json::to_string_pretty(&schema)))
+}
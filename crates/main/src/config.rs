@@ -1,12 +1,17 @@
-use std::{collections::HashMap, fmt, fs, io, path::Path};
+use std::{
+	collections::{BTreeMap, HashMap},
+	env, fmt, fs, io,
+	path::Path
+};
 
 use niji_macros::IntoLua;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::types::color::Color;
+use crate::{theme::remap, types::color::Color};
 
-#[derive(Debug, Clone, IntoLua, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, IntoLua, Serialize, Deserialize, JsonSchema)]
 #[lua_with("ToString::to_string")]
 #[serde(rename_all = "lowercase")]
 pub enum ColorScheme {
@@ -23,28 +28,104 @@ impl fmt::Display for ColorScheme {
 	}
 }
 
-#[derive(Debug, Clone, IntoLua, Serialize, Deserialize)]
+#[derive(Debug, Clone, IntoLua, Serialize, Deserialize, JsonSchema)]
 pub struct UiTheme {
+	/// Whether the theme should be considered light or dark
 	pub color_scheme: ColorScheme,
+	/// The main background color
 	pub background: Color,
+	/// The background color of surfaces that appear on top of `background` (such as panels
+	/// or cards)
 	pub surface: Color,
+	/// The primary accent color of the UI
 	pub primary: Color,
+	/// The secondary accent color of the UI
 	pub secondary: Color,
+	/// The color of borders around certain elements. May be set to transparent (`#00000000`)
+	/// to remove borders.
 	pub border: Color,
+	/// The color of drop shadow around certain elements. May be set to transparent
+	/// (`#00000000`) to remove drop shadows.
 	pub shadow: Color,
+	/// The color of text appearing on `background`
 	pub text_background: Color,
+	/// The color of text appearing on `surface`
 	pub text_surface: Color,
+	/// The color of text appearing on `primary`
 	pub text_primary: Color,
+	/// The color indicating a successful action. Usually a shade of green.
 	pub success: Color,
+	/// The color used for informative user feedback
 	pub info: Color,
+	/// The color used for warning messages. Usually a shade of yellow or orange.
 	pub warning: Color,
+	/// The color used for error messages and states. Usually a shade of red.
 	pub error: Color,
+	/// The color of text appearing on `success`
 	pub text_success: Color,
+	/// The color of text appearing on `info`
 	pub text_info: Color,
+	/// The color of text appearing on `warning`
 	pub text_warning: Color,
+	/// The color of text appearing on `error`
 	pub text_error: Color
 }
 
+impl UiTheme {
+	/// Interpolates between the colors of `a` and `b`, where `t` ranges from 0 (`a`) to 1
+	/// (`b`). The color scheme is taken from whichever of the two is closer.
+	pub fn blend(a: &Self, b: &Self, t: f32) -> Self {
+		let blend = |a, b| Color::blend(a, b, t);
+		Self {
+			color_scheme: if t < 0.5 {
+				a.color_scheme.clone()
+			} else {
+				b.color_scheme.clone()
+			},
+			background: blend(a.background, b.background),
+			surface: blend(a.surface, b.surface),
+			primary: blend(a.primary, b.primary),
+			secondary: blend(a.secondary, b.secondary),
+			border: blend(a.border, b.border),
+			shadow: blend(a.shadow, b.shadow),
+			text_background: blend(a.text_background, b.text_background),
+			text_surface: blend(a.text_surface, b.text_surface),
+			text_primary: blend(a.text_primary, b.text_primary),
+			success: blend(a.success, b.success),
+			info: blend(a.info, b.info),
+			warning: blend(a.warning, b.warning),
+			error: blend(a.error, b.error),
+			text_success: blend(a.text_success, b.text_success),
+			text_info: blend(a.text_info, b.text_info),
+			text_warning: blend(a.text_warning, b.text_warning),
+			text_error: blend(a.text_error, b.text_error)
+		}
+	}
+
+	/// All colors of the UI theme with their names, in the order they are declared in
+	pub fn colors(&self) -> [(&'static str, Color); 17] {
+		[
+			("background", self.background),
+			("surface", self.surface),
+			("primary", self.primary),
+			("secondary", self.secondary),
+			("border", self.border),
+			("shadow", self.shadow),
+			("text_background", self.text_background),
+			("text_surface", self.text_surface),
+			("text_primary", self.text_primary),
+			("success", self.success),
+			("info", self.info),
+			("warning", self.warning),
+			("error", self.error),
+			("text_success", self.text_success),
+			("text_info", self.text_info),
+			("text_warning", self.text_warning),
+			("text_error", self.text_error)
+		]
+	}
+}
+
 fn color_display(text: &str, bg_col: Color, fg_col: Color) -> String {
 	format!(
 		"\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m {text} \x1b[0m",
@@ -87,7 +168,7 @@ impl fmt::Display for UiTheme {
 	}
 }
 
-#[derive(Debug, Clone, IntoLua, Serialize, Deserialize)]
+#[derive(Debug, Clone, IntoLua, Serialize, Deserialize, JsonSchema)]
 pub struct Terminal {
 	pub black: Color,
 	pub red: Color,
@@ -104,7 +185,221 @@ pub struct Terminal {
 	pub bright_blue: Color,
 	pub bright_magenta: Color,
 	pub bright_cyan: Color,
-	pub bright_white: Color
+	pub bright_white: Color,
+
+	/// The default text color
+	pub foreground: Color,
+	/// The default background color
+	pub background: Color,
+	/// The color of the cursor
+	pub cursor: Color,
+	/// The color of text under the cursor
+	pub cursor_text: Color,
+	/// The background color of selected text
+	pub selection_background: Color,
+	/// The color of selected text
+	pub selection_foreground: Color,
+
+	/// The 256 colors of the extended palette. The first 16 are the colors above, followed by
+	/// a 6x6x6 color cube and a grayscale ramp that are interpolated from them.
+	#[serde(skip)]
+	pub palette_256: Vec<Color>
+}
+
+impl Terminal {
+	/// Interpolates between the colors of `a` and `b`, where `t` ranges from 0 (`a`) to 1
+	/// (`b`)
+	pub fn blend(a: &Self, b: &Self, t: f32) -> Self {
+		let blend = |a, b| Color::blend(a, b, t);
+		Self {
+			palette_256: a
+				.palette_256
+				.iter()
+				.zip(&b.palette_256)
+				.map(|(a, b)| blend(*a, *b))
+				.collect(),
+			black: blend(a.black, b.black),
+			red: blend(a.red, b.red),
+			green: blend(a.green, b.green),
+			yellow: blend(a.yellow, b.yellow),
+			blue: blend(a.blue, b.blue),
+			magenta: blend(a.magenta, b.magenta),
+			cyan: blend(a.cyan, b.cyan),
+			white: blend(a.white, b.white),
+			bright_black: blend(a.bright_black, b.bright_black),
+			bright_red: blend(a.bright_red, b.bright_red),
+			bright_green: blend(a.bright_green, b.bright_green),
+			bright_yellow: blend(a.bright_yellow, b.bright_yellow),
+			bright_blue: blend(a.bright_blue, b.bright_blue),
+			bright_magenta: blend(a.bright_magenta, b.bright_magenta),
+			bright_cyan: blend(a.bright_cyan, b.bright_cyan),
+			bright_white: blend(a.bright_white, b.bright_white),
+			foreground: blend(a.foreground, b.foreground),
+			background: blend(a.background, b.background),
+			cursor: blend(a.cursor, b.cursor),
+			cursor_text: blend(a.cursor_text, b.cursor_text),
+			selection_background: blend(a.selection_background, b.selection_background),
+			selection_foreground: blend(a.selection_foreground, b.selection_foreground)
+		}
+	}
+
+	/// All terminal colors with their names, in the order of their ANSI color indices
+	pub fn colors(&self) -> [(&'static str, Color); 16] {
+		[
+			("black", self.black),
+			("red", self.red),
+			("green", self.green),
+			("yellow", self.yellow),
+			("blue", self.blue),
+			("magenta", self.magenta),
+			("cyan", self.cyan),
+			("white", self.white),
+			("bright_black", self.bright_black),
+			("bright_red", self.bright_red),
+			("bright_green", self.bright_green),
+			("bright_yellow", self.bright_yellow),
+			("bright_blue", self.bright_blue),
+			("bright_magenta", self.bright_magenta),
+			("bright_cyan", self.bright_cyan),
+			("bright_white", self.bright_white)
+		]
+	}
+}
+
+#[derive(Debug, Clone, IntoLua, Serialize, Deserialize, JsonSchema)]
+#[lua_with("Syntax::roles")]
+pub struct Syntax {
+	/// Keywords, like `if`, `fn` or `return`
+	pub keyword: Color,
+	/// String and character literals
+	pub string: Color,
+	/// Comments
+	pub comment: Color,
+	/// Names of functions and methods
+	pub function: Color,
+	/// Names of types, classes and interfaces
+	pub r#type: Color,
+	/// Constants, numbers and booleans
+	pub constant: Color,
+	/// Names of variables and parameters
+	pub variable: Color,
+	/// Lines added in a diff
+	pub diff_add: Color,
+	/// Lines changed in a diff
+	pub diff_change: Color,
+	/// Lines deleted in a diff
+	pub diff_delete: Color,
+	/// Additional roles, like `operator`, for programs that distinguish more roles than the
+	/// ones above
+	#[serde(flatten)]
+	pub extra: BTreeMap<String, Color>
+}
+
+impl Syntax {
+	/// Interpolates between the colors of `a` and `b`, where `t` ranges from 0 (`a`) to 1
+	/// (`b`)
+	pub fn blend(a: &Self, b: &Self, t: f32) -> Self {
+		let blend = |a, b| Color::blend(a, b, t);
+		Self {
+			keyword: blend(a.keyword, b.keyword),
+			string: blend(a.string, b.string),
+			comment: blend(a.comment, b.comment),
+			function: blend(a.function, b.function),
+			r#type: blend(a.r#type, b.r#type),
+			constant: blend(a.constant, b.constant),
+			variable: blend(a.variable, b.variable),
+			diff_add: blend(a.diff_add, b.diff_add),
+			diff_change: blend(a.diff_change, b.diff_change),
+			diff_delete: blend(a.diff_delete, b.diff_delete),
+			extra: blend_named(&a.extra, &b.extra, t)
+		}
+	}
+
+	/// All syntax colors by role, including the additional ones
+	pub fn roles(&self) -> BTreeMap<String, Color> {
+		let mut roles = self.extra.clone();
+		roles.extend(self.colors().map(|(role, color)| (role.to_string(), color)));
+		roles
+	}
+
+	/// All syntax colors with their roles, in the order they are declared in
+	pub fn colors(&self) -> [(&'static str, Color); 10] {
+		[
+			("keyword", self.keyword),
+			("string", self.string),
+			("comment", self.comment),
+			("function", self.function),
+			("type", self.r#type),
+			("constant", self.constant),
+			("variable", self.variable),
+			("diff_add", self.diff_add),
+			("diff_change", self.diff_change),
+			("diff_delete", self.diff_delete)
+		]
+	}
+
+	/// Renders a short code sample in the syntax colors, on top of `background`
+	pub fn sample(&self, background: Color, text: Color) -> String {
+		let lines: [&[(Option<Color>, &str)]; 6] = [
+			&[(Some(self.comment), "// Greets everyone in the list")],
+			&[
+				(Some(self.keyword), "fn "),
+				(Some(self.function), "greet"),
+				(None, "("),
+				(Some(self.variable), "names"),
+				(None, ": &["),
+				(Some(self.r#type), "String"),
+				(None, "]) {")
+			],
+			&[
+				(None, "    "),
+				(Some(self.keyword), "for "),
+				(Some(self.variable), "name "),
+				(Some(self.keyword), "in "),
+				(Some(self.variable), "names"),
+				(None, "."),
+				(Some(self.function), "iter"),
+				(None, "()."),
+				(Some(self.function), "take"),
+				(None, "("),
+				(Some(self.constant), "3"),
+				(None, ") {")
+			],
+			&[
+				(None, "        "),
+				(Some(self.function), "println!"),
+				(None, "("),
+				(Some(self.string), "\"Hello, {name}!\""),
+				(None, ");")
+			],
+			&[(None, "    }")],
+			&[(None, "}")]
+		];
+
+		let width = 48;
+		let mut sample = String::new();
+		for line in lines {
+			let mut length = 0;
+			for (color, part) in line {
+				let color = color.unwrap_or(text);
+				sample.push_str(&format!(
+					"\x1b[48;2;{};{};{}m\x1b[38;2;{};{};{}m{part}",
+					background.r, background.g, background.b, color.r, color.g, color.b
+				));
+				length += part.chars().count();
+			}
+			sample.push_str(&" ".repeat(width - length));
+			sample.push_str("\x1b[0m\n");
+		}
+
+		sample.push_str(&format!(
+			"{}{}{}",
+			color_display("+ added", self.diff_add, background),
+			color_display("~ changed", self.diff_change, background),
+			color_display("- deleted", self.diff_delete, background)
+		));
+		sample
+	}
 }
 
 fn colored_square(color: Color) -> String {
@@ -133,26 +428,210 @@ impl fmt::Display for Terminal {
 		f.write_str(&colored_square(self.bright_cyan))?;
 		f.write_str(&colored_square(self.bright_white))?;
 
+		writeln!(f)?;
+
+		f.write_str(&color_display("Cursor", self.cursor, self.cursor_text))?;
+		f.write_str(&color_display(
+			"Selection",
+			self.selection_background,
+			self.selection_foreground
+		))?;
+
 		Ok(())
 	}
 }
 
-#[derive(Debug, Clone, IntoLua, Serialize, Deserialize)]
+/// Information about a theme that isn't used to apply it
+#[derive(Debug, Default, Clone, PartialEq, IntoLua, Serialize, Deserialize, JsonSchema)]
+pub struct ThemeMeta {
+	/// The name of the theme for display purposes, like "Catppuccin Latte"
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub name: Option<String>,
+	/// The author of the theme or of the color scheme it is based on
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub author: Option<String>,
+	/// The license of the theme
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub license: Option<String>,
+	/// A short description of the theme
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub description: Option<String>,
+	/// Keywords that describe the theme, like "pastel" or "high-contrast"
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub tags: Vec<String>,
+	/// The family of themes this theme is a variant of. Themes in a family directory, like
+	/// `catppuccin/latte`, are part of that family without setting this.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub family: Option<String>
+}
+
+impl ThemeMeta {
+	pub fn is_empty(&self) -> bool {
+		*self == Self::default()
+	}
+}
+
+impl fmt::Display for ThemeMeta {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match (&self.name, &self.author) {
+			(Some(name), Some(author)) => writeln!(f, "{name} by {author}")?,
+			(Some(name), None) => writeln!(f, "{name}")?,
+			(None, Some(author)) => writeln!(f, "By {author}")?,
+			(None, None) => ()
+		}
+		if let Some(description) = &self.description {
+			writeln!(f, "{description}")?;
+		}
+		if !self.tags.is_empty() {
+			writeln!(f, "Tags: {}", self.tags.join(", "))?;
+		}
+		if let Some(license) = &self.license {
+			writeln!(f, "License: {license}")?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, IntoLua, Serialize, Deserialize, JsonSchema)]
 pub struct Theme {
 	#[serde(skip)]
 	pub name: Option<String>,
 
+	/// The name of a theme to inherit all values from that this theme doesn't set itself
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub extends: Option<String>,
+
+	#[serde(default, skip_serializing_if = "ThemeMeta::is_empty")]
+	pub meta: ThemeMeta,
+
 	pub ui: UiTheme,
-	pub terminal: Terminal
+	pub terminal: Terminal,
+
+	/// Additional named colors, for modules that need more colors than `ui` and `terminal`
+	/// provide
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub palette: BTreeMap<String, Color>,
+
+	/// Colors for syntax highlighting in editors and similar programs
+	pub syntax: Syntax,
+
+	/// Maps the keys of inherited values, like `ui.primary`, to the name of the theme
+	/// they were inherited from
+	#[serde(skip)]
+	#[lua_skip]
+	pub inherited: BTreeMap<String, String>,
+
+	/// The keys of values that weren't set by the theme or its parents, and were derived from
+	/// other values instead
+	#[serde(skip)]
+	#[lua_skip]
+	pub derived: Vec<String>
+}
+
+impl Theme {
+	/// Interpolates between the themes `a` and `b` in the OKLCH color space, where `t` ranges
+	/// from 0 (`a`) to 1 (`b`). Values that can't be interpolated, like the name and the color
+	/// scheme, are taken from whichever theme is closer. Palette colors that only one of the
+	/// themes defines are kept as they are.
+	pub fn blend(a: &Self, b: &Self, t: f32) -> Self {
+		let closer = if t < 0.5 { a } else { b };
+
+		Self {
+			name: closer.name.clone(),
+			extends: None,
+			meta: ThemeMeta::default(),
+			ui: UiTheme::blend(&a.ui, &b.ui, t),
+			terminal: Terminal::blend(&a.terminal, &b.terminal, t),
+			palette: blend_named(&a.palette, &b.palette, t),
+			syntax: Syntax::blend(&a.syntax, &b.syntax, t),
+			inherited: BTreeMap::new(),
+			derived: Vec::new()
+		}
+	}
+}
+
+/// Blends the colors with the same name in `a` and `b`, and keeps the colors that only one of
+/// them defines as they are
+fn blend_named(
+	a: &BTreeMap<String, Color>,
+	b: &BTreeMap<String, Color>,
+	t: f32
+) -> BTreeMap<String, Color> {
+	let mut blended = a.clone();
+	for (name, color) in b {
+		blended
+			.entry(name.clone())
+			.and_modify(|a| *a = Color::blend(*a, *color, t))
+			.or_insert(*color);
+	}
+	blended
 }
 
 impl fmt::Display for Theme {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		write!(f, "{}\n{}", self.ui, self.terminal)
+		if !self.meta.is_empty() {
+			writeln!(f, "{}", self.meta)?;
+		}
+
+		write!(f, "{}\n{}", self.ui, self.terminal)?;
+
+		if !self.palette.is_empty() {
+			write!(f, "\n\nPalette:")?;
+			for (name, color) in &self.palette {
+				write!(f, "\n{} {name}", colored_square(*color))?;
+			}
+		}
+
+		write!(
+			f,
+			"\n\nSyntax:\n{}",
+			self.syntax
+				.sample(self.ui.background, self.ui.text_background)
+		)?;
+		for (role, color) in &self.syntax.extra {
+			write!(f, "\n{} {role}", colored_square(*color))?;
+		}
+
+		if let Some(parent) = &self.extends {
+			write!(f, "\n\nExtends \"{parent}\"")?;
+		}
+
+		let mut inherited: BTreeMap<&str, BTreeMap<&str, Vec<&str>>> = BTreeMap::new();
+		for (key, source) in &self.inherited {
+			let (section, name) = key.split_once('.').unwrap_or(("", key));
+			inherited
+				.entry(source)
+				.or_default()
+				.entry(section)
+				.or_default()
+				.push(name);
+		}
+
+		for (source, sections) in inherited {
+			write!(f, "\n\nInherited from \"{source}\":")?;
+			for (section, names) in sections {
+				write!(f, "\n  {section}: {}", names.join(", "))?;
+			}
+		}
+
+		let mut derived: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+		for key in &self.derived {
+			let (section, name) = key.split_once('.').unwrap_or(("", key));
+			derived.entry(section).or_default().push(name);
+		}
+
+		if !derived.is_empty() {
+			write!(f, "\n\nDerived automatically:")?;
+			for (section, names) in derived {
+				write!(f, "\n  {section}: {}", names.join(", "))?;
+			}
+		}
+
+		Ok(())
 	}
 }
 
-#[derive(Debug, Default, Clone, IntoLua, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, IntoLua, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum ModuleConfigValue {
 	#[default]
@@ -165,8 +644,48 @@ pub enum ModuleConfigValue {
 	Map(HashMap<String, ModuleConfigValue>)
 }
 
+impl ModuleConfigValue {
+	/// Expands `~`, `$VAR` and `${VAR:-default}` in string values. Variables that aren't set
+	/// are left as they are, so that values like shell commands can still refer to them.
+	/// Strings that start with `./` or `../` are considered paths, and are resolved relative
+	/// to `base_dir`.
+	fn expand(&mut self, base_dir: &Path) {
+		match self {
+			Self::String(string) => {
+				let expanded =
+					shellexpand::env_with_context_no_errors(string, |var| env::var(var).ok());
+				let expanded = shellexpand::tilde(&expanded).into_owned();
+
+				*string = if expanded.starts_with("./") || expanded.starts_with("../") {
+					let relative = expanded.strip_prefix("./").unwrap_or(&expanded);
+					base_dir.join(relative).to_string_lossy().into_owned()
+				} else {
+					expanded
+				};
+			}
+			Self::Vec(values) => {
+				for value in values {
+					value.expand(base_dir);
+				}
+			}
+			Self::Map(values) => {
+				for value in values.values_mut() {
+					value.expand(base_dir);
+				}
+			}
+			_ => ()
+		}
+	}
+}
+
 pub type ModuleConfig = HashMap<String, ModuleConfigValue>;
 
+fn expand_module_config(module_config: &mut ModuleConfig, base_dir: &Path) {
+	for value in module_config.values_mut() {
+		value.expand(base_dir);
+	}
+}
+
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DisableReloads {
@@ -189,20 +708,116 @@ impl DisableReloads {
 	}
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Switches between a light and a dark theme at fixed times of the day, or at sunrise and
+/// sunset
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Schedule {
+	/// The theme to use during the day. May also be the name of a theme family, in which case
+	/// its light variant is used.
+	pub light: String,
+
+	/// The theme to use during the night. May also be the name of a theme family, in which case
+	/// its dark variant is used.
+	pub dark: String,
+
+	/// The time of day at which to switch to the light theme, like `"07:00"`
+	pub light_at: Option<String>,
+
+	/// The time of day at which to switch to the dark theme, like `"19:00"`
+	pub dark_at: Option<String>,
+
+	/// The latitude used to calculate sunrise and sunset, in degrees north
+	pub latitude: Option<f64>,
+
+	/// The longitude used to calculate sunrise and sunset, in degrees east
+	pub longitude: Option<f64>
+}
+
+/// Replaces the accent color of every theme
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Accent {
+	/// The color to use instead of `ui.primary`. It is made lighter or darker if it doesn't
+	/// contrast enough with the background of a theme.
+	pub color: Color,
+
+	/// Whether to also replace `ui.secondary` with a color derived from the accent
+	#[serde(default)]
+	pub secondary: bool,
+
+	/// Whether to shift the hue of terminal colors that are close to the accent to match it
+	#[serde(default)]
+	pub shift_terminal: bool
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Config {
+	/// The names of the modules to activate
 	pub modules: Vec<String>,
 
+	/// Modules that shouldn't be automatically reloaded. Can be `"none"`, `"all"`, or a list
+	/// of module names.
 	#[serde(default)]
 	pub disable_reloads: DisableReloads,
 
+	/// Config options that apply to all modules
 	#[serde(default)]
 	pub global: ModuleConfig,
 
+	/// An accent color to use in every theme
+	pub accent: Option<Accent>,
+
+	/// Themes to apply to certain modules instead of the current theme, by module name. May
+	/// also be the names of theme families, in which case the variant with the color scheme of
+	/// the current theme is used.
+	#[serde(default)]
+	pub module_themes: BTreeMap<String, String>,
+
+	/// When to switch between a light and a dark theme automatically
+	pub schedule: Option<Schedule>,
+
 	#[serde(flatten)]
 	pub module_config: HashMap<String, ModuleConfig>
 }
 
+impl Config {
+	/// Reads the config file at `path`, and expands environment variables and relative
+	/// paths in the module config values.
+	pub fn read<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+		let mut config: Self = read(&path)?;
+
+		let base_dir = path.as_ref().parent().unwrap_or(Path::new(""));
+		expand_module_config(&mut config.global, base_dir);
+		for module_config in config.module_config.values_mut() {
+			expand_module_config(module_config, base_dir);
+		}
+
+		Ok(config)
+	}
+
+	pub fn resolve_module_config(&self, module: &str) -> ModuleConfig {
+		let mut module_config = self.global.clone();
+		let Some(specific) = self.module_config.get(module) else {
+			return module_config;
+		};
+
+		for (key, value) in specific {
+			match (module_config.get_mut(key), value) {
+				// Color mappings are merged, so that modules only need to set the roles they
+				// change
+				(Some(ModuleConfigValue::Map(global)), ModuleConfigValue::Map(specific))
+					if key == remap::COLORS_KEY =>
+				{
+					global.extend(specific.clone())
+				}
+				_ => {
+					module_config.insert(key.clone(), value.clone());
+				}
+			}
+		}
+		module_config
+	}
+}
+
 #[derive(Debug, Error)]
 pub enum Error {
 	#[error("Failed to read {0}: {1}")]
@@ -223,3 +838,99 @@ where
 		.map_err(|e| Error::Parse(path.as_ref().display().to_string(), Box::new(e)))?;
 	Ok(config)
 }
+
+#[cfg(test)]
+mod tests {
+	use std::env;
+
+	use super::*;
+	use crate::theme::generate::{self, ThemeSeeds};
+
+	fn generate(background: Color, color_scheme: ColorScheme) -> Theme {
+		generate::generate(&ThemeSeeds {
+			background,
+			primary: Color::new_rgba(40, 120, 220, 255),
+			secondary: None,
+			color_scheme: Some(color_scheme),
+			palette: Vec::new()
+		})
+	}
+
+	fn expanded(value: &str) -> ModuleConfigValue {
+		let mut value = ModuleConfigValue::String(value.to_string());
+		value.expand(Path::new("/home/user/.config/niji"));
+		value
+	}
+
+	#[test]
+	fn should_expand_env_vars() {
+		env::set_var("NIJI_TEST_WALLPAPERS", "/wallpapers");
+
+		assert!(matches!(
+			expanded("$NIJI_TEST_WALLPAPERS/wp1.png"),
+			ModuleConfigValue::String(s) if s == "/wallpapers/wp1.png"
+		));
+		assert!(matches!(
+			expanded("${NIJI_TEST_UNSET:-/default}/wp1.png"),
+			ModuleConfigValue::String(s) if s == "/default/wp1.png"
+		));
+		assert!(matches!(
+			expanded("tail -f $NIJI_TEST_UNSET/wob.sock | wob"),
+			ModuleConfigValue::String(s) if s == "tail -f $NIJI_TEST_UNSET/wob.sock | wob"
+		));
+	}
+
+	#[test]
+	fn should_blend_themes() {
+		let mut light = generate(Color::new_rgba(250, 250, 250, 255), ColorScheme::Light);
+		light
+			.palette
+			.insert("accent".to_string(), Color::new_rgba(255, 0, 0, 255));
+		let dark = generate(Color::new_rgba(20, 20, 20, 255), ColorScheme::Dark);
+
+		let start = Theme::blend(&light, &dark, 0.0);
+		assert_eq!(start.ui.background, light.ui.background);
+		assert_eq!(start.ui.color_scheme, ColorScheme::Light);
+
+		let end = Theme::blend(&light, &dark, 1.0);
+		assert_eq!(end.terminal.red, dark.terminal.red);
+		assert_eq!(end.ui.color_scheme, ColorScheme::Dark);
+		assert_eq!(end.palette["accent"], Color::new_rgba(255, 0, 0, 255));
+
+		let middle = Theme::blend(&light, &dark, 0.5);
+		assert!(middle.ui.background.r < light.ui.background.r);
+		assert!(middle.ui.background.r > dark.ui.background.r);
+	}
+
+	#[test]
+	fn should_keep_additional_syntax_roles() {
+		let theme = generate(Color::new_rgba(20, 20, 20, 255), ColorScheme::Dark);
+		let mut table = toml::Table::try_from(&theme).unwrap();
+		table["syntax"]
+			.as_table_mut()
+			.unwrap()
+			.insert("operator".to_string(), "#ff0000".into());
+
+		let theme: Theme = table.try_into().unwrap();
+		assert_eq!(
+			theme.syntax.extra.get("operator"),
+			Some(&Color::new_rgba(255, 0, 0, 255))
+		);
+		assert_eq!(
+			theme.syntax.roles().get("keyword"),
+			Some(&theme.syntax.keyword)
+		);
+	}
+
+	#[test]
+	fn should_resolve_relative_paths() {
+		assert!(matches!(
+			expanded("./wallpapers/wp1.png"),
+			ModuleConfigValue::String(s) if s == "/home/user/.config/niji/wallpapers/wp1.png"
+		));
+		assert!(matches!(
+			expanded("Fira Sans"),
+			ModuleConfigValue::String(s) if s == "Fira Sans"
+		));
+	}
+}
@@ -0,0 +1,258 @@
+use std::{collections::HashSet, fs, io, path::Path, rc::Rc};
+
+use log::{debug, info, warn};
+use schemars::schema::RootSchema;
+use thiserror::Error;
+use toml_edit::{Array, DocumentMut, Item, Key, Table, TableLike, Value};
+
+use crate::{
+	config::{self, Config, ModuleConfig},
+	files::Files,
+	module::Module,
+	schema::{self, ModuleOptions}
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error("Failed to read {0}: {1}")]
+	Read(String, io::Error),
+
+	#[error("Failed to write {0}: {1}")]
+	Write(String, io::Error),
+
+	#[error("Invalid syntax in {0}: {1}")]
+	Parse(String, Box<toml_edit::TomlError>),
+
+	#[error("\"{0}\" is not a valid config key: {1}")]
+	InvalidKey(String, Box<toml_edit::TomlError>),
+
+	#[error("Cannot set \"{0}\", because \"{1}\" is not a table")]
+	NotATable(String, String),
+
+	#[error("The \"modules\" option must be a list of module names")]
+	InvalidModuleList,
+
+	#[error("Module \"{0}\" doesn't exist!")]
+	UnknownModule(String),
+
+	#[error(transparent)]
+	Config(#[from] config::Error)
+}
+
+pub struct ConfigManager {
+	files: Rc<Files>
+}
+
+impl ConfigManager {
+	const MODULES_KEY: &'static str = "modules";
+	const GLOBAL_KEY: &'static str = "global";
+	const WALLPAPER_KEY: &'static str = "wallpaper";
+
+	pub fn new(files: Rc<Files>) -> Self {
+		Self { files }
+	}
+
+	pub fn source(&self) -> Result<String, Error> {
+		Ok(self.read_document()?.to_string())
+	}
+
+	pub fn get(&self, key: &str) -> Result<Option<Item>, Error> {
+		let path = Self::parse_key(key)?;
+		let document = self.read_document()?;
+
+		let mut item = document.as_item();
+		for segment in &path {
+			let Some(next) = item.get(segment.get()) else {
+				return Ok(None);
+			};
+			item = next;
+		}
+
+		Ok(Some(item.clone()))
+	}
+
+	pub fn set(&self, key: &str, value: &str) -> Result<(), Error> {
+		let path = Self::parse_key(key)?;
+		let mut document = self.read_document()?;
+
+		let (last, parents) = path.split_last().unwrap();
+		let mut table: &mut dyn TableLike = document.as_table_mut();
+		for (i, segment) in parents.iter().enumerate() {
+			let item = table.entry_format(segment).or_insert_with(|| {
+				let mut new_table = Table::new();
+				new_table.set_implicit(true);
+				Item::Table(new_table)
+			});
+			table = item
+				.as_table_like_mut()
+				.ok_or_else(|| Error::NotATable(key.to_string(), Self::join_key(&parents[..=i])))?;
+		}
+
+		let mut new_value = Self::parse_value(value);
+		match table.get_mut(last) {
+			Some(Item::Value(old_value)) => {
+				*new_value.decor_mut() = old_value.decor().clone();
+				*old_value = new_value;
+			}
+			Some(old_item) => *old_item = Item::Value(new_value),
+			None => {
+				table.insert(last, Item::Value(new_value));
+			}
+		}
+
+		debug!("Setting config option {key} to {value}");
+		self.write_document(&document)
+	}
+
+	pub fn add_modules(&self, names: &[String]) -> Result<(), Error> {
+		for name in names {
+			if !self.files.iter_modules().any(|l| &l.name == name) {
+				return Err(Error::UnknownModule(name.clone()));
+			}
+		}
+
+		let mut document = self.read_document()?;
+		let modules = Self::modules_array(&mut document)?;
+
+		for name in names {
+			if modules.iter().any(|m| m.as_str() == Some(name)) {
+				warn!("Module \"{name}\" is already active");
+				continue;
+			}
+			modules.push(name.as_str());
+			info!("Activated module \"{name}\"");
+		}
+
+		self.write_document(&document)
+	}
+
+	pub fn remove_modules(&self, names: &[String]) -> Result<(), Error> {
+		let mut document = self.read_document()?;
+		let modules = Self::modules_array(&mut document)?;
+
+		for name in names {
+			let Some(index) = modules.iter().position(|m| m.as_str() == Some(name)) else {
+				warn!("Module \"{name}\" is not active");
+				continue;
+			};
+			modules.remove(index);
+			info!("Deactivated module \"{name}\"");
+		}
+
+		self.write_document(&document)
+	}
+
+	/// Adds `wallpaper` to the per-theme wallpaper map for `theme`. If a single wallpaper is
+	/// set for all themes, it becomes the default of the new map.
+	pub fn set_theme_wallpaper(&self, theme: &str, wallpaper: &Path) -> Result<(), Error> {
+		let mut document = self.read_document()?;
+		let full_key = format!("{}.{}", Self::GLOBAL_KEY, Self::WALLPAPER_KEY);
+
+		let global = document
+			.entry(Self::GLOBAL_KEY)
+			.or_insert_with(|| {
+				let mut new_table = Table::new();
+				new_table.set_implicit(true);
+				Item::Table(new_table)
+			})
+			.as_table_like_mut()
+			.ok_or_else(|| Error::NotATable(full_key.clone(), Self::GLOBAL_KEY.to_string()))?;
+
+		if let Some(default) = global.get(Self::WALLPAPER_KEY).and_then(Item::as_str) {
+			let mut new_table = Table::new();
+			new_table.insert("default", toml_edit::value(default));
+			global.remove(Self::WALLPAPER_KEY);
+			global.insert(Self::WALLPAPER_KEY, Item::Table(new_table));
+		}
+		let wallpapers = global
+			.entry(Self::WALLPAPER_KEY)
+			.or_insert_with(|| Item::Table(Table::new()));
+		let wallpapers = wallpapers
+			.as_table_like_mut()
+			.ok_or_else(|| Error::NotATable(full_key.clone(), full_key.clone()))?;
+
+		let wallpaper = wallpaper.display().to_string();
+		debug!("Setting wallpaper of theme \"{theme}\" to {wallpaper}");
+		wallpapers.insert(theme, toml_edit::value(wallpaper));
+
+		self.write_document(&document)
+	}
+
+	pub fn resolve_module_config(&self, module: &str) -> Result<ModuleConfig, Error> {
+		let config = Config::read(self.files.config_file())?;
+		Ok(config.resolve_module_config(module))
+	}
+
+	pub fn schema(&self) -> RootSchema {
+		let mut seen = HashSet::new();
+		let mut modules = Vec::new();
+
+		for location in self.files.iter_modules() {
+			if !seen.insert(location.name.clone()) {
+				continue;
+			}
+
+			match Module::read_options(&location.path) {
+				Ok(options) => modules.push(ModuleOptions {
+					name: location.name,
+					options
+				}),
+				Err(err) => warn!("Ignoring options of module \"{}\": {err}", location.name)
+			}
+		}
+
+		modules.sort_by(|a, b| a.name.cmp(&b.name));
+		schema::config_schema(&modules)
+	}
+
+	fn modules_array(document: &mut DocumentMut) -> Result<&mut Array, Error> {
+		document
+			.entry(Self::MODULES_KEY)
+			.or_insert_with(|| Item::Value(Value::Array(Array::new())))
+			.as_array_mut()
+			.ok_or(Error::InvalidModuleList)
+	}
+
+	fn parse_key(key: &str) -> Result<Vec<Key>, Error> {
+		Key::parse(key).map_err(|e| Error::InvalidKey(key.to_string(), Box::new(e)))
+	}
+
+	fn join_key(path: &[Key]) -> String {
+		path.iter().map(|k| k.get()).collect::<Vec<_>>().join(".")
+	}
+
+	fn parse_value(value: &str) -> Value {
+		// Anything that isn't valid TOML on its own is treated as a plain string, so that
+		// `niji config set global.font_family "Fira Sans"` works without extra quoting.
+		match value.parse::<Value>() {
+			Ok(mut parsed) => {
+				parsed.decor_mut().clear();
+				parsed
+			}
+			Err(..) => Value::from(value)
+		}
+	}
+
+	fn read_document(&self) -> Result<DocumentMut, Error> {
+		let path = self.files.config_file();
+		let path_name = path.display().to_string();
+
+		if !path.exists() {
+			debug!("{path_name} doesn't exist yet; starting with an empty config");
+			let mut document = DocumentMut::new();
+			document.insert(Self::MODULES_KEY, Item::Value(Value::Array(Array::new())));
+			return Ok(document);
+		}
+
+		let source = fs::read_to_string(path).map_err(|e| Error::Read(path_name.clone(), e))?;
+		source
+			.parse()
+			.map_err(|e| Error::Parse(path_name, Box::new(e)))
+	}
+
+	fn write_document(&self, document: &DocumentMut) -> Result<(), Error> {
+		let path = self.files.config_file();
+		fs::write(path, document.to_string())
+			.map_err(|e| Error::Write(path.display().to_string(), e))
+	}
+}
@@ -0,0 +1,338 @@
+use std::{
+	collections::BTreeMap,
+	fs,
+	io::{self, BufRead, BufReader, Write},
+	net::Shutdown,
+	os::unix::net::{UnixListener, UnixStream},
+	path::{Path, PathBuf},
+	process,
+	time::{Duration, SystemTime}
+};
+
+use log::{debug, error, info};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+	app::{self, NijiApp},
+	config::ColorScheme,
+	theme_manager::ThemeFilter
+};
+
+/// How long the daemon waits for a client to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error("The daemon needs $XDG_RUNTIME_DIR to be set")]
+	NoRuntimeDir,
+
+	#[error("The daemon is already running at {0}")]
+	AlreadyRunning(String),
+
+	#[error("The daemon is not running")]
+	NotRunning,
+
+	#[error("Failed to listen on {0}: {1}")]
+	Listen(String, io::Error),
+
+	#[error("Failed to communicate with the daemon: {0}")]
+	Io(#[from] io::Error),
+
+	#[error("Received an invalid message: {0}")]
+	Protocol(#[from] serde_json::Error),
+
+	#[error("{0}")]
+	App(#[from] app::Error)
+}
+
+/// A command sent to the daemon. Each request is a single line of JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+	Apply {
+		reload: bool,
+		force: bool,
+		modules: Option<Vec<String>>
+	},
+	SetTheme {
+		name: String,
+		color_scheme: Option<ColorScheme>,
+		apply: bool,
+		reload: bool
+	},
+	ToggleScheme {
+		apply: bool,
+		reload: bool
+	},
+	SelectTheme {
+		selection: ThemeSelection,
+		apply: bool,
+		reload: bool
+	},
+	Status,
+	Stop
+}
+
+/// How `Request::SelectTheme` picks the theme to switch to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "selection", content = "filter", rename_all = "snake_case")]
+pub enum ThemeSelection {
+	/// The theme that was set before the current one
+	Previous,
+
+	/// The theme after the current one in the sorted list of themes matching the filter
+	Next(ThemeFilter),
+
+	/// The theme before the current one in the sorted list of themes matching the filter
+	Prev(ThemeFilter),
+
+	/// A random theme matching the filter that wasn't set recently
+	Random(ThemeFilter)
+}
+
+/// The answer to a `Request`. Each response is a single line of JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "result", content = "value", rename_all = "snake_case")]
+pub enum Response {
+	Done,
+
+	/// The name of the theme that was set
+	Theme(String),
+
+	Status(Status),
+
+	Error(String)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Status {
+	/// The name of the current theme, if one is set
+	pub theme: Option<String>,
+
+	/// The names of the active modules
+	pub modules: Vec<String>,
+
+	/// The themes pinned to modules in the config, by module name
+	#[serde(default)]
+	pub pinned_themes: BTreeMap<String, String>,
+
+	/// The process ID of the daemon, if the status was reported by the daemon
+	pub daemon_pid: Option<u32>
+}
+
+/// Runs `request` using `app`. This is what the daemon does for each request, and what the
+/// CLI does instead if the daemon isn't running.
+pub fn handle_request(app: &NijiApp, request: Request) -> Response {
+	let result = match request {
+		Request::Apply {
+			reload,
+			force,
+			modules
+		} => app
+			.apply(reload, force, modules.as_deref())
+			.map(|_| Response::Done),
+		Request::SetTheme {
+			name,
+			color_scheme,
+			apply,
+			reload
+		} => switch_theme(app, app.set_theme(&name, color_scheme), apply, reload),
+		Request::ToggleScheme { apply, reload } => {
+			switch_theme(app, app.toggle_scheme(), apply, reload)
+		}
+		Request::SelectTheme {
+			selection,
+			apply,
+			reload
+		} => {
+			let theme = match selection {
+				ThemeSelection::Previous => app.previous_theme(),
+				ThemeSelection::Next(filter) => app.cycle_theme(&filter, true),
+				ThemeSelection::Prev(filter) => app.cycle_theme(&filter, false),
+				ThemeSelection::Random(filter) => app.random_theme(&filter)
+			};
+			switch_theme(app, theme, apply, reload)
+		}
+		Request::Status => Ok(Response::Status(Status {
+			theme: app.current_theme().ok().and_then(|theme| theme.name),
+			modules: app.active_modules().to_vec(),
+			pinned_themes: app.module_themes().clone(),
+			daemon_pid: None
+		})),
+		Request::Stop => Ok(Response::Done)
+	};
+
+	result.unwrap_or_else(|err| Response::Error(err.to_string()))
+}
+
+/// Applies the theme that was switched to, if `apply` is set
+fn switch_theme(
+	app: &NijiApp,
+	theme: Result<String, app::Error>,
+	apply: bool,
+	reload: bool
+) -> Result<Response, app::Error> {
+	let theme = theme?;
+	if apply {
+		app.apply(reload, false, None)?;
+	}
+	Ok(Response::Theme(theme))
+}
+
+/// Reads a message from `stream`, or returns `None` if the other side closed the connection
+/// without sending one
+fn read_message<T>(stream: &UnixStream) -> Result<Option<T>, Error>
+where
+	T: for<'de> Deserialize<'de>
+{
+	let mut line = String::new();
+	if BufReader::new(stream).read_line(&mut line)? == 0 {
+		return Ok(None);
+	}
+	Ok(Some(serde_json::from_str(&line)?))
+}
+
+fn write_message<T: Serialize>(mut stream: &UnixStream, message: &T) -> Result<(), Error> {
+	let mut line = serde_json::to_string(message)?;
+	line.push('\n');
+	stream.write_all(line.as_bytes())?;
+	Ok(())
+}
+
+/// A connection to a running daemon
+pub struct Client {
+	stream: UnixStream
+}
+
+impl Client {
+	/// Connects to the daemon listening on `socket`, or returns `None` if it isn't running
+	pub fn connect(socket: &Path) -> Option<Self> {
+		let stream = UnixStream::connect(socket).ok()?;
+		debug!("Connected to the daemon at {}", socket.display());
+		Some(Self { stream })
+	}
+
+	pub fn request(self, request: &Request) -> Result<Response, Error> {
+		write_message(&self.stream, request)?;
+		self.stream.shutdown(Shutdown::Write)?;
+		read_message(&self.stream)?.ok_or_else(|| {
+			Error::Io(io::Error::new(
+				io::ErrorKind::UnexpectedEof,
+				"The daemon closed the connection without responding"
+			))
+		})
+	}
+}
+
+/// Removes the socket file once the daemon stops
+struct SocketGuard<'a>(&'a Path);
+
+impl Drop for SocketGuard<'_> {
+	fn drop(&mut self) {
+		if let Err(err) = fs::remove_file(self.0) {
+			error!("Failed to remove {}: {err}", self.0.display());
+		}
+	}
+}
+
+struct Daemon {
+	app: NijiApp,
+	config_modified: Option<SystemTime>
+}
+
+impl Daemon {
+	fn new() -> Result<Self, Error> {
+		let app = NijiApp::init()?;
+		let config_modified = modified(app.config_file());
+		Ok(Self {
+			app,
+			config_modified
+		})
+	}
+
+	/// Reloads the app if the config file changed since it was last loaded, so that changes
+	/// made with `niji config` or an editor are picked up
+	fn reload_if_changed(&mut self) -> Result<(), Error> {
+		let config_modified = modified(self.app.config_file());
+		if config_modified == self.config_modified {
+			return Ok(());
+		}
+
+		info!("The config file changed, reloading");
+		self.app = NijiApp::init()?;
+		self.config_modified = config_modified;
+		Ok(())
+	}
+
+	/// Answers the request sent over `stream`. Returns whether the daemon should stop.
+	fn serve(&mut self, stream: &UnixStream) -> Result<bool, Error> {
+		stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+		// Clients that only check whether the daemon is running close the connection without
+		// sending anything
+		let Some(request) = read_message::<Request>(stream)? else {
+			return Ok(false);
+		};
+		debug!("Received request {request:?}");
+
+		let stop = matches!(request, Request::Stop);
+		let response = match self.reload_if_changed() {
+			Ok(()) => match handle_request(&self.app, request) {
+				Response::Status(status) => Response::Status(Status {
+					daemon_pid: Some(process::id()),
+					..status
+				}),
+				response => response
+			},
+			Err(err) => Response::Error(err.to_string())
+		};
+
+		write_message(stream, &response)?;
+		Ok(stop)
+	}
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Runs the daemon in the foreground, listening on `socket` until it receives a `Stop`
+/// request
+pub fn run(socket: &Path) -> Result<(), Error> {
+	if UnixStream::connect(socket).is_ok() {
+		return Err(Error::AlreadyRunning(socket.display().to_string()));
+	}
+
+	// The socket file of a daemon that didn't shut down cleanly would prevent binding
+	if socket.exists() {
+		fs::remove_file(socket).map_err(|e| Error::Listen(socket.display().to_string(), e))?;
+	}
+
+	let mut daemon = Daemon::new()?;
+
+	let listener =
+		UnixListener::bind(socket).map_err(|e| Error::Listen(socket.display().to_string(), e))?;
+	let _guard = SocketGuard(socket);
+	info!("Listening on {}", socket.display());
+
+	for stream in listener.incoming() {
+		let result = stream
+			.map_err(Error::from)
+			.and_then(|stream| daemon.serve(&stream));
+
+		match result {
+			Ok(true) => break,
+			Ok(false) => (),
+			Err(err) => error!("{err}")
+		}
+	}
+
+	info!("Stopping the daemon");
+	Ok(())
+}
+
+/// The path of the daemon's socket
+pub fn socket_file() -> Result<PathBuf, Error> {
+	NijiApp::socket_file()?.ok_or(Error::NoRuntimeDir)
+}
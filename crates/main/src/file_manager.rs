@@ -1,6 +1,7 @@
 use log::{debug, info, warn};
 use niji_console::prompt;
 use std::{
+	cell::RefCell,
 	collections::{hash_map::DefaultHasher, HashMap},
 	fs::{self, File},
 	hash::{Hash, Hasher},
@@ -28,7 +29,9 @@ pub enum Error {
 }
 
 pub struct FileManager {
-	files: Rc<Files>
+	files: Rc<Files>,
+	/// The files written since they were last taken with `take_written_files`
+	written_files: RefCell<Vec<PathBuf>>
 }
 
 impl FileManager {
@@ -39,7 +42,10 @@ impl FileManager {
 			})?;
 		}
 
-		Ok(Self { files })
+		Ok(Self {
+			files,
+			written_files: RefCell::new(Vec::new())
+		})
 	}
 
 	pub fn write_managed(&self, path: &Path, string: &str) -> Result<(), Error> {
@@ -47,10 +53,23 @@ impl FileManager {
 
 		if !path.exists() {
 			debug!("Creating new managed file at {}", path.display());
-			self.init_new_file(&mut managed_files, path, string)
+			self.init_new_file(&mut managed_files, path, string)?;
 		} else {
-			self.manage_existing_file(&mut managed_files, path, string)
+			self.manage_existing_file(&mut managed_files, path, string)?;
 		}
+
+		self.record_written(path.to_path_buf());
+		Ok(())
+	}
+
+	/// Records that a file was written, for files that are written without `write_managed`
+	pub fn record_written(&self, path: PathBuf) {
+		self.written_files.borrow_mut().push(path);
+	}
+
+	/// The files written since the last call to this function
+	pub fn take_written_files(&self) -> Vec<PathBuf> {
+		self.written_files.take()
 	}
 
 	fn init_new_file(
@@ -20,8 +20,12 @@ pub enum Error {
 pub struct Files {
 	config_file: PathBuf,
 	current_theme_file: PathBuf,
+	theme_history_file: PathBuf,
 	managed_files_file: PathBuf,
+	module_fingerprints_file: PathBuf,
+	socket_file: Option<PathBuf>,
 	output_dir: PathBuf,
+	custom_themes_dir: PathBuf,
 	themes_dirs: Vec<PathBuf>,
 	modules_dirs: Vec<PathBuf>,
 }
@@ -36,7 +40,10 @@ impl Files {
 	const PREFIX: &'static str = "niji";
 	const CONFIG_FILE: &'static str = "config.toml";
 	const CURRENT_THEME_FILE: &'static str = "current_theme.txt";
+	const THEME_HISTORY_FILE: &'static str = "theme_history.txt";
 	const MANAGED_FILES_FILE: &'static str = "managed_files.csv";
+	const MODULE_FINGERPRINTS_FILE: &'static str = "module_fingerprints.csv";
+	const SOCKET_FILE: &'static str = "niji.sock";
 	const THEMES_DIR: &'static str = "themes";
 	const THEME_MAIN_FILE_NAME: &'static str = "theme.toml";
 	const MODULES_DIR: &'static str = "modules";
@@ -52,14 +59,20 @@ impl Files {
 
 		let config_file = config_dir.join(Self::CONFIG_FILE);
 		let current_theme_file = state_dir.join(Self::CURRENT_THEME_FILE);
+		let theme_history_file = state_dir.join(Self::THEME_HISTORY_FILE);
 		let managed_files_file = state_dir.join(Self::MANAGED_FILES_FILE);
+		let module_fingerprints_file = state_dir.join(Self::MODULE_FINGERPRINTS_FILE);
+		let socket_file = xdg
+			.runtime_dir
+			.as_ref()
+			.map(|dir| dir.join(Self::SOCKET_FILE));
 		let custom_themes_dir = config_dir.join(Self::THEMES_DIR);
 		let custom_modules_dir = config_dir.join(Self::MODULES_DIR);
 
 		init_dir(&custom_themes_dir)?;
 		init_dir(&custom_modules_dir)?;
 
-		let mut themes_dirs = vec![custom_themes_dir];
+		let mut themes_dirs = vec![custom_themes_dir.clone()];
 		let mut modules_dirs = vec![custom_modules_dir];
 
 		let data_dirs = xdg
@@ -78,7 +91,11 @@ impl Files {
 			config_file,
 			output_dir: data_dir,
 			current_theme_file,
+			theme_history_file,
 			managed_files_file,
+			module_fingerprints_file,
+			socket_file,
+			custom_themes_dir,
 			themes_dirs,
 			modules_dirs,
 		})
@@ -94,16 +111,54 @@ impl Files {
 		&self.current_theme_file
 	}
 
+	/// The themes that were set, one per line, with the most recent one last
+	#[inline]
+	pub fn theme_history_file(&self) -> &Path {
+		&self.theme_history_file
+	}
+
 	#[inline]
 	pub fn managed_files_file(&self) -> &Path {
 		&self.managed_files_file
 	}
 
+	#[inline]
+	pub fn module_fingerprints_file(&self) -> &Path {
+		&self.module_fingerprints_file
+	}
+
+	/// The socket the daemon listens on, if `$XDG_RUNTIME_DIR` is set
+	#[inline]
+	pub fn socket_file(&self) -> Option<&Path> {
+		self.socket_file.as_deref()
+	}
+
 	#[inline]
 	pub fn output_dir(&self) -> &Path {
 		&self.output_dir
 	}
 
+	/// The directories themes are searched in, starting with the one for custom themes
+	#[inline]
+	pub fn themes_dirs(&self) -> &[PathBuf] {
+		&self.themes_dirs
+	}
+
+	/// The file a custom theme is saved to. Themes named `family/variant` are saved as
+	/// `family/variant.toml`, so that they are found as variants of the family.
+	pub fn custom_theme_file(&self, name: &str) -> PathBuf {
+		match name.split_once('/') {
+			Some((family, variant)) => self
+				.custom_themes_dir
+				.join(family)
+				.join(format!("{variant}.toml")),
+			None => self
+				.custom_themes_dir
+				.join(name)
+				.join(Self::THEME_MAIN_FILE_NAME),
+		}
+	}
+
 	pub fn iter_themes(&self) -> impl Iterator<Item = Location> + '_ {
 		let toplevel_themes = find_files(&self.themes_dirs).map(|f| Location {
 			name: f
@@ -123,7 +178,28 @@ impl Files {
 				path: d.join(Self::THEME_MAIN_FILE_NAME),
 			})
 			.filter(|l| l.path.exists());
-		toplevel_themes.chain(nested_themes)
+		// Theme directories may contain variants of the theme in additional files, like
+		// `catppuccin/latte.toml`, which are named like `catppuccin/latte`
+		let variant_themes = find_dirs(&self.themes_dirs).flat_map(|d| {
+			let family = d
+				.file_name()
+				.unwrap_or_default()
+				.to_string_lossy()
+				.to_string();
+			find_files([d])
+				.filter(|f| {
+					f.extension().is_some_and(|ext| ext == "toml")
+						&& f.file_name().unwrap_or_default() != Self::THEME_MAIN_FILE_NAME
+				})
+				.map(move |f| Location {
+					name: format!(
+						"{family}/{}",
+						f.file_stem().unwrap_or_default().to_string_lossy()
+					),
+					path: f,
+				})
+		});
+		toplevel_themes.chain(nested_themes).chain(variant_themes)
 	}
 
 	pub fn iter_modules(&self) -> impl Iterator<Item = Location> + '_ {
@@ -87,6 +87,10 @@ impl FilesystemApi {
 		info!("Outputting to {}", path.display());
 		fs::create_dir_all(path.parent().unwrap()).map_err(mlua::Error::runtime)?;
 		fs::write(&path, content).map_err(mlua::Error::runtime)?;
+
+		let file_mgr = lua.app_data_ref::<Rc<FileManager>>().unwrap();
+		file_mgr.record_written(path.clone());
+
 		Ok(path.to_string_lossy().into_owned())
 	}
 
@@ -1,6 +1,8 @@
+use log::warn;
 use mlua::{IntoLua, Lua};
 
 use super::ApiModule;
+use crate::theme_manager::RENAMED_THEMES;
 
 pub struct UtilApi;
 
@@ -20,9 +22,19 @@ impl UtilApi {
 			return Ok(default);
 		};
 
-		Ok(table
-			.get::<_, Option<mlua::Value>>(name)?
-			.unwrap_or(default))
+		if let Some(value) = table.get::<_, Option<mlua::Value>>(name.as_str())? {
+			return Ok(value);
+		}
+
+		// Values for built-in themes that were renamed may still use the old name
+		for (old_name, _) in RENAMED_THEMES.iter().filter(|(_, new)| *new == name) {
+			if let Some(value) = table.get::<_, Option<mlua::Value>>(*old_name)? {
+				warn!("\"{old_name}\" was renamed to \"{name}\", please update your config");
+				return Ok(value);
+			}
+		}
+
+		Ok(default)
 	}
 
 	fn font_size<'lua>(
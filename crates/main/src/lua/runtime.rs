@@ -64,6 +64,15 @@ impl<'lua> LuaModule<'lua> {
 		Ok(matches!(value, mlua::Value::Function(..)))
 	}
 
+	/// Whether the module sets `key` to `true`
+	pub fn has_flag(&'lua self, key: &str) -> mlua::Result<bool> {
+		let table = self.get_table()?;
+		Ok(matches!(
+			table.get::<_, Option<mlua::Value>>(key)?,
+			Some(mlua::Value::Boolean(true))
+		))
+	}
+
 	pub fn call<A, R>(&'lua self, key: &str, args: A) -> mlua::Result<R>
 	where
 		A: IntoLuaMulti<'lua>,
@@ -1,15 +1,21 @@
 mod app;
 mod cli;
 mod config;
+mod config_manager;
+mod daemon;
 mod file_manager;
 mod files;
 mod lua;
 mod module;
 mod module_manager;
+mod schedule;
+mod schema;
 mod template;
+mod theme;
 mod theme_manager;
 mod types;
 mod utils;
+mod watch;
 
 fn main() {
 	cli::run();
@@ -9,8 +9,9 @@ use log::debug;
 use thiserror::Error;
 
 use crate::{
-	config::{ModuleConfig, Theme},
-	lua::runtime::{LuaModule, LuaRuntime}
+	config::{self, ModuleConfig, Theme},
+	lua::runtime::{LuaModule, LuaRuntime},
+	theme::remap
 };
 
 #[derive(Debug, Error)]
@@ -21,6 +22,9 @@ pub enum LoadError {
 	#[error("Missing dependency: {0}")]
 	MissingDependency(String),
 
+	#[error("Failed to read module options: {0}")]
+	Options(#[from] config::Error),
+
 	#[error("{0}")]
 	LuaErr(#[from] mlua::Error)
 }
@@ -30,6 +34,9 @@ pub enum ExecError {
 	#[error("Module is missing an apply function")]
 	NoApply,
 
+	#[error(transparent)]
+	Remap(#[from] remap::Error),
+
 	#[error(transparent)]
 	LuaErr(#[from] mlua::Error)
 }
@@ -38,6 +45,7 @@ pub struct Module<'lua>(LuaModule<'lua>);
 
 impl<'lua> Module<'lua> {
 	const DEPS_FILE: &'static str = "deps.txt";
+	const OPTIONS_FILE: &'static str = "options.toml";
 
 	pub fn load(runtime: &'lua LuaRuntime, path: &Path) -> Result<Self, LoadError> {
 		Self::check_dependencies(path)?;
@@ -49,11 +57,18 @@ impl<'lua> Module<'lua> {
 		self.0.has_function("reload").unwrap_or(false)
 	}
 
+	/// Whether the module declares that reloading it is fast and unobtrusive enough to do
+	/// several times per second, which is needed for theme transitions
+	pub fn reloads_fast(&self) -> bool {
+		self.0.has_flag("fast_reload").unwrap_or(false)
+	}
+
 	pub fn apply(&self, config: ModuleConfig, theme: Theme) -> Result<(), ExecError> {
 		if !self.0.has_function("apply")? {
 			return Err(ExecError::NoApply);
 		}
 
+		let theme = remap::remap(&theme, &config)?;
 		Ok(self.0.call("apply", (config, theme))?)
 	}
 
@@ -61,6 +76,17 @@ impl<'lua> Module<'lua> {
 		Ok(self.0.call("reload", config)?)
 	}
 
+	/// Reads the config options the module declares in its `options.toml`, as a map from
+	/// option names to JSON schemas. Returns an empty map if the module doesn't declare any.
+	pub fn read_options(path: &Path) -> Result<toml::Table, LoadError> {
+		let options_file = path.join(Self::OPTIONS_FILE);
+		if !options_file.exists() {
+			return Ok(toml::Table::new());
+		}
+
+		Ok(config::read(options_file)?)
+	}
+
 	fn check_dependencies(path: &Path) -> Result<(), LoadError> {
 		let deps_file = path.join(Self::DEPS_FILE);
 		if !deps_file.exists() {
@@ -1,6 +1,16 @@
-use std::{collections::HashSet, path::PathBuf, rc::Rc, sync::Mutex};
+use std::{
+	collections::{hash_map::DefaultHasher, HashMap, HashSet},
+	fmt, fs,
+	hash::{Hash, Hasher},
+	io,
+	path::{Path, PathBuf},
+	rc::Rc,
+	sync::Mutex,
+	thread,
+	time::{Duration, Instant},
+};
 
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use niji_console::heading;
 use thiserror::Error;
 
@@ -35,8 +45,54 @@ struct ModuleDescriptor {
 	path: PathBuf,
 }
 
+/// The fingerprint of a module's inputs when it was last applied successfully
+#[derive(Clone)]
+struct AppliedFingerprint {
+	fingerprint: u64,
+	reloaded: bool,
+	/// The files the module wrote
+	outputs: Vec<PathBuf>,
+}
+
+/// The result of applying a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApplyOutcome {
+	/// The module couldn't be applied
+	Failed,
+	/// The module was applied, but not reloaded, because reloading wasn't requested or
+	/// failed
+	Applied,
+	/// The module was applied, and reloaded if it supports that
+	Reloaded,
+}
+
+impl AppliedFingerprint {
+	/// The fingerprint to store for a module after applying it with `outcome`. Modules that
+	/// failed to apply don't get one, so that they are applied again on the next run.
+	fn new(fingerprint: u64, outcome: ApplyOutcome, outputs: Vec<PathBuf>) -> Option<Self> {
+		match outcome {
+			ApplyOutcome::Failed => None,
+			ApplyOutcome::Applied | ApplyOutcome::Reloaded => Some(Self {
+				fingerprint,
+				reloaded: outcome == ApplyOutcome::Reloaded,
+				outputs,
+			}),
+		}
+	}
+
+	/// Whether a module applied with this fingerprint doesn't need to be applied again. A
+	/// module that wasn't reloaded still needs to be reloaded, and one whose files were
+	/// deleted since needs to write them again.
+	fn is_up_to_date(&self, fingerprint: u64, reload: bool) -> bool {
+		self.fingerprint == fingerprint
+			&& (self.reloaded || !reload)
+			&& self.outputs.iter().all(|output| output.exists())
+	}
+}
+
 pub struct ModuleManager {
 	files: Rc<Files>,
+	file_manager: Rc<FileManager>,
 	active_modules: Mutex<Vec<ModuleDescriptor>>,
 	lua_runtime: LuaRuntime,
 }
@@ -64,16 +120,22 @@ impl ModuleManager {
 
 		Ok(Self {
 			files: Rc::clone(&files),
+			file_manager,
 			active_modules: Mutex::new(active_modules),
 			lua_runtime,
 		})
 	}
 
-	pub fn apply(
+	/// Applies the active modules, or the given modules if `modules` is set. Modules for which
+	/// `pinned_theme` returns a theme are applied with it instead of `theme`. Modules whose inputs
+	/// haven't changed since they were last applied are skipped, unless `force` is set.
+	pub fn apply<E: fmt::Display>(
 		&self,
 		config: &Config,
 		theme: &Theme,
+		pinned_theme: impl Fn(&str) -> Option<Result<Theme, E>>,
 		reload: bool,
+		force: bool,
 		modules: Option<&[String]>,
 	) -> Result<(), Error> {
 		let mut remaining = HashSet::<String>::new();
@@ -81,12 +143,22 @@ impl ModuleManager {
 			remaining.extend(modules.iter().cloned())
 		}
 
+		let mut fingerprints = self.read_fingerprints();
+
 		for module_descr in &*self.active_modules.lock().unwrap() {
 			if modules.is_some() && !remaining.remove(&module_descr.name.clone()) {
 				continue;
 			}
 
-			self.apply_module(module_descr, config, theme, reload);
+			self.apply_with_pinned_theme(
+				module_descr,
+				config,
+				theme,
+				&pinned_theme,
+				reload,
+				force,
+				&mut fingerprints,
+			);
 		}
 
 		if modules.is_some() {
@@ -96,13 +168,100 @@ impl ModuleManager {
 					&mut self.active_modules.lock().unwrap(),
 					&mod_name,
 				)?;
-				self.apply_module(&module_descr, config, theme, reload);
+				self.apply_with_pinned_theme(
+					&module_descr,
+					config,
+					theme,
+					&pinned_theme,
+					reload,
+					force,
+					&mut fingerprints,
+				);
 			}
 		}
 
+		self.write_fingerprints(&fingerprints);
+
 		Ok(())
 	}
 
+	/// Gradually changes the active modules that reload fast from `from` towards `to`, by
+	/// applying `steps - 1` blended themes over `duration`. The final theme isn't applied, so
+	/// that the caller can apply it to all modules as usual.
+	pub fn transition(
+		&self,
+		config: &Config,
+		from: &Theme,
+		to: &Theme,
+		duration: Duration,
+		steps: u32,
+	) {
+		let mut modules = Vec::new();
+		for module_descr in &*self.active_modules.lock().unwrap() {
+			// Modules with a pinned theme don't change when the current theme does
+			if config.disable_reloads.is_disabled(&module_descr.name)
+				|| config.module_themes.contains_key(&module_descr.name)
+			{
+				continue;
+			}
+			match Module::load(&self.lua_runtime, &module_descr.path) {
+				Ok(module) if module.reloads_fast() && module.can_reload() => {
+					modules.push((module_descr.name.clone(), module))
+				}
+				Ok(_) => debug!("Module {} doesn't support transitions", module_descr.name),
+				Err(err) => warn!("Skipping module {} in transition: {err}", module_descr.name),
+			}
+		}
+
+		if modules.is_empty() {
+			debug!("No active modules support transitions");
+			return;
+		}
+		info!(
+			"Transitioning {}...",
+			modules
+				.iter()
+				.map(|(name, _)| name.as_str())
+				.collect::<Vec<_>>()
+				.join(", ")
+		);
+
+		// Waiting until fixed points in time keeps the duration accurate even if applying the
+		// modules takes a while
+		let start = Instant::now();
+		let wait_for_step = |step: u32| {
+			let time = start + duration * step / steps;
+			thread::sleep(time.saturating_duration_since(Instant::now()));
+		};
+
+		for step in 1..steps {
+			wait_for_step(step);
+			let theme = Theme::blend(from, to, step as f32 / steps as f32);
+
+			modules.retain(|(name, module)| {
+				let module_config = config.resolve_module_config(name);
+				let result = module
+					.apply(module_config.clone(), theme.clone())
+					.and_then(|_| module.reload(module_config));
+				if let Err(err) = &result {
+					warn!("Stopping the transition of module {name}: {err}");
+				}
+				result.is_ok()
+			});
+		}
+		wait_for_step(steps);
+	}
+
+	/// The names and directories of the active modules
+	pub fn active_module_dirs(&self) -> Vec<(String, PathBuf)> {
+		self.active_modules
+			.lock()
+			.unwrap()
+			.iter()
+			.map(|module_descr| (module_descr.name.clone(), module_descr.path.clone()))
+			.collect()
+	}
+
 	fn activate(
 		files: &Files,
 		active_modules: &mut Vec<ModuleDescriptor>,
@@ -126,13 +285,84 @@ impl ModuleManager {
 		Ok(module_descr)
 	}
 
-	fn apply_module(
+	/// Applies a module with the theme pinned to it, or with `theme` if it has none. A pinned
+	/// theme that can't be loaded only fails this module.
+	#[allow(clippy::too_many_arguments)]
+	fn apply_with_pinned_theme<E: fmt::Display>(
+		&self,
+		module_descr: &ModuleDescriptor,
+		config: &Config,
+		theme: &Theme,
+		pinned_theme: &impl Fn(&str) -> Option<Result<Theme, E>>,
+		reload: bool,
+		force: bool,
+		fingerprints: &mut HashMap<String, AppliedFingerprint>,
+	) {
+		match pinned_theme(&module_descr.name) {
+			None => self.apply_if_changed(module_descr, config, theme, reload, force, fingerprints),
+			Some(Ok(pinned)) => {
+				self.apply_if_changed(module_descr, config, &pinned, reload, force, fingerprints)
+			}
+			Some(Err(err)) => {
+				heading!("{}", module_descr.name);
+				error!(
+					"Failed to load the theme pinned to {}: {err}",
+					module_descr.name
+				);
+				niji_console::println!();
+			}
+		}
+	}
+
+	fn apply_if_changed(
 		&self,
 		module_descr: &ModuleDescriptor,
 		config: &Config,
 		theme: &Theme,
 		reload: bool,
+		force: bool,
+		fingerprints: &mut HashMap<String, AppliedFingerprint>,
 	) {
+		let fingerprint = match Self::fingerprint(module_descr, config, theme) {
+			Ok(fingerprint) => Some(fingerprint),
+			Err(err) => {
+				debug!("Failed to fingerprint module {}: {err}", module_descr.name);
+				None
+			}
+		};
+
+		let up_to_date = fingerprint.is_some_and(|fingerprint| {
+			fingerprints
+				.get(&module_descr.name)
+				.is_some_and(|applied| applied.is_up_to_date(fingerprint, reload))
+		});
+		if up_to_date && !force {
+			info!(
+				"Module {} is up to date, skipping. Use --force to apply it anyway.",
+				module_descr.name
+			);
+			return;
+		}
+
+		fingerprints.remove(&module_descr.name);
+		self.file_manager.take_written_files();
+		let outcome = self.apply_module(module_descr, config, theme, reload);
+		let outputs = self.file_manager.take_written_files();
+		if let Some(applied) = fingerprint
+			.and_then(|fingerprint| AppliedFingerprint::new(fingerprint, outcome, outputs))
+		{
+			fingerprints.insert(module_descr.name.clone(), applied);
+		}
+	}
+
+	/// Applies a module, and reloads it if `reload` is set
+	fn apply_module(
+		&self,
+		module_descr: &ModuleDescriptor,
+		config: &Config,
+		theme: &Theme,
+		reload: bool,
+	) -> ApplyOutcome {
 		heading!("{}", module_descr.name);
 
 		let module = match Module::load(&self.lua_runtime, &module_descr.path) {
@@ -140,20 +370,17 @@ impl ModuleManager {
 			Err(error) => {
 				error!("{error}");
 				niji_console::println!();
-				return;
+				return ApplyOutcome::Failed;
 			}
 		};
 
-		let mut module_config = config.global.clone();
-		if let Some(specific) = config.module_config.get(&module_descr.name) {
-			module_config.extend(specific.clone());
-		}
+		let module_config = config.resolve_module_config(&module_descr.name);
 
 		if let Err(err) = module.apply(module_config.clone(), theme.clone()) {
 			error!("{err}");
 			error!("Aborting module execution");
 			niji_console::println!();
-			return;
+			return ApplyOutcome::Failed;
 		}
 		if reload {
 			if config.disable_reloads.is_disabled(&module_descr.name) {
@@ -168,6 +395,7 @@ impl ModuleManager {
 					error!("{err}");
 					error!("Reloading of {} failed", module_descr.name);
 					niji_console::println!();
+					return ApplyOutcome::Applied;
 				}
 			} else {
 				debug!("Module {} does not support reloading.", module_descr.name)
@@ -175,6 +403,95 @@ impl ModuleManager {
 		}
 		info!("Done!");
 		niji_console::println!();
+
+		if reload {
+			ApplyOutcome::Reloaded
+		} else {
+			ApplyOutcome::Applied
+		}
+	}
+
+	/// A hash of everything applying a module depends on: the files of the module, its
+	/// config, the theme, and the version of niji. `DefaultHasher` isn't guaranteed to be
+	/// stable across Rust releases, which is fine for a cache; a changed hash only means that
+	/// the modules are applied once more.
+	fn fingerprint(
+		module_descr: &ModuleDescriptor,
+		config: &Config,
+		theme: &Theme,
+	) -> io::Result<u64> {
+		let mut hasher = DefaultHasher::new();
+		env!("CARGO_PKG_VERSION").hash(&mut hasher);
+
+		// Converting to a JSON value first sorts the keys of maps
+		let module_config = config.resolve_module_config(&module_descr.name);
+		serde_json::to_value(module_config)?
+			.to_string()
+			.hash(&mut hasher);
+		theme.name.hash(&mut hasher);
+		serde_json::to_value(theme)?.to_string().hash(&mut hasher);
+
+		hash_dir(&module_descr.path, &module_descr.path, &mut hasher)?;
+
+		Ok(hasher.finish())
+	}
+
+	fn read_fingerprints(&self) -> HashMap<String, AppliedFingerprint> {
+		let path = self.files.module_fingerprints_file();
+		if !path.exists() {
+			return HashMap::new();
+		}
+
+		// The outputs of a module take up the remaining fields of its row
+		let fingerprints = csv::ReaderBuilder::new()
+			.has_headers(false)
+			.flexible(true)
+			.from_path(path)
+			.and_then(|mut reader| {
+				reader
+					.deserialize::<(String, u64, bool, Vec<PathBuf>)>()
+					.map(|row| {
+						row.map(|(name, fingerprint, reloaded, outputs)| {
+							(
+								name,
+								AppliedFingerprint {
+									fingerprint,
+									reloaded,
+									outputs,
+								},
+							)
+						})
+					})
+					.collect()
+			});
+
+		fingerprints.unwrap_or_else(|err| {
+			warn!("Failed to read {}: {err}", path.display());
+			HashMap::new()
+		})
+	}
+
+	fn write_fingerprints(&self, fingerprints: &HashMap<String, AppliedFingerprint>) {
+		let path = self.files.module_fingerprints_file();
+		let result = csv::WriterBuilder::new()
+			.flexible(true)
+			.from_path(path)
+			.and_then(|mut writer| {
+				for (name, applied) in fingerprints {
+					writer.serialize((
+						name,
+						applied.fingerprint,
+						applied.reloaded,
+						&applied.outputs,
+					))?;
+				}
+				writer.flush()?;
+				Ok(())
+			});
+
+		if let Err(err) = result {
+			warn!("Failed to write {}: {err}", path.display());
+		}
 	}
 
 	fn find_module_dir(files: &Files, name: &str) -> Option<PathBuf> {
@@ -186,3 +503,46 @@ impl ModuleManager {
 		None
 	}
 }
+
+/// Hashes the paths and contents of all files in `dir`, in a stable order
+fn hash_dir(root: &Path, dir: &Path, hasher: &mut impl Hasher) -> io::Result<()> {
+	let mut entries = fs::read_dir(dir)?
+		.map(|entry| entry.map(|entry| entry.path()))
+		.collect::<io::Result<Vec<_>>>()?;
+	entries.sort();
+
+	for path in entries {
+		if path.is_dir() {
+			hash_dir(root, &path, hasher)?;
+		} else {
+			path.strip_prefix(root).unwrap_or(&path).hash(hasher);
+			fs::read(&path)?.hash(hasher);
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_reload_again_after_failed_reload() {
+		let applied = AppliedFingerprint::new(42, ApplyOutcome::Applied, Vec::new()).unwrap();
+		assert!(!applied.is_up_to_date(42, true));
+		assert!(applied.is_up_to_date(42, false));
+
+		let reloaded = AppliedFingerprint::new(42, ApplyOutcome::Reloaded, Vec::new()).unwrap();
+		assert!(reloaded.is_up_to_date(42, true));
+		assert!(!reloaded.is_up_to_date(43, true));
+
+		assert!(AppliedFingerprint::new(42, ApplyOutcome::Failed, Vec::new()).is_none());
+	}
+
+	#[test]
+	fn should_apply_again_when_outputs_are_missing() {
+		let outputs = vec![PathBuf::from("/nonexistent/niji/output.txt")];
+		let applied = AppliedFingerprint::new(42, ApplyOutcome::Reloaded, outputs).unwrap();
+		assert!(!applied.is_up_to_date(42, false));
+	}
+}
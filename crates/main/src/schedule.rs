@@ -0,0 +1,339 @@
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use thiserror::Error;
+
+use crate::config::{ColorScheme, Schedule};
+
+/// How far ahead to look for the next transition, for schedules near the poles where the sun
+/// may not rise or set for months
+const MAX_DAYS_AHEAD: u64 = 366;
+
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error("No schedule is configured. Add a [schedule] table to your config.toml to set one.")]
+	NotConfigured,
+
+	#[error("Invalid time \"{0}\" in the schedule: {1}. Times must look like \"07:30\".")]
+	InvalidTime(String, chrono::ParseError),
+
+	#[error(
+		"The schedule needs either \"light_at\" and \"dark_at\", or \"latitude\" and \"longitude\""
+	)]
+	MissingTimes,
+
+	#[error(
+		"The schedule can't have both fixed times and coordinates. Remove either \"light_at\" and \
+		 \"dark_at\", or \"latitude\" and \"longitude\"."
+	)]
+	ConflictingTimes,
+
+	#[error("The schedule switches to the light and the dark theme at the same time ({0})")]
+	SameTimes(String),
+
+	#[error("Invalid coordinates {0}, {1} in the schedule")]
+	InvalidCoordinates(f64, f64)
+}
+
+/// When the themes of a schedule change
+#[derive(Debug, Clone, Copy)]
+enum Times {
+	Fixed { light: NaiveTime, dark: NaiveTime },
+	Solar { latitude: f64, longitude: f64 }
+}
+
+impl Times {
+	fn new(schedule: &Schedule) -> Result<Self, Error> {
+		let fixed = (schedule.light_at.as_deref(), schedule.dark_at.as_deref());
+		let solar = (schedule.latitude, schedule.longitude);
+
+		match (fixed, solar) {
+			((Some(light), Some(dark)), (None, None)) => {
+				let light = parse_time(light)?;
+				let dark = parse_time(dark)?;
+				if light == dark {
+					return Err(Error::SameTimes(light.format("%H:%M").to_string()));
+				}
+				Ok(Self::Fixed { light, dark })
+			}
+			((None, None), (Some(latitude), Some(longitude))) => {
+				if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+					return Err(Error::InvalidCoordinates(latitude, longitude));
+				}
+				Ok(Self::Solar {
+					latitude,
+					longitude
+				})
+			}
+			((None, None), _) | (_, (None, None)) => Err(Error::MissingTimes),
+			_ => Err(Error::ConflictingTimes)
+		}
+	}
+}
+
+fn parse_time(time: &str) -> Result<NaiveTime, Error> {
+	NaiveTime::parse_from_str(time, "%H:%M").map_err(|e| Error::InvalidTime(time.to_string(), e))
+}
+
+/// A point in time at which the schedule switches to the theme with `color_scheme`
+#[derive(Debug, Clone)]
+pub struct Transition<Tz: TimeZone> {
+	pub time: DateTime<Tz>,
+	pub color_scheme: ColorScheme
+}
+
+/// What the schedule selects at a given point in time
+#[derive(Debug, Clone)]
+pub struct ScheduleState<Tz: TimeZone> {
+	pub color_scheme: ColorScheme,
+
+	/// The next time the color scheme changes, if it changes within the next year
+	pub next_transition: Option<DateTime<Tz>>
+}
+
+/// The theme a schedule selects at a given point in time
+#[derive(Debug, Clone)]
+pub struct ScheduledTheme {
+	pub name: String,
+	pub color_scheme: ColorScheme,
+
+	/// The next time the schedule switches themes, if it does within the next year
+	pub next_transition: Option<DateTime<Local>>
+}
+
+/// The position of the sun over the course of a day
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SunTimes {
+	Rises {
+		sunrise: DateTime<Utc>,
+		sunset: DateTime<Utc>
+	},
+	AlwaysUp,
+	AlwaysDown
+}
+
+/// Calculates sunrise and sunset on `date` at the given coordinates, using the sunrise
+/// equation. This is accurate to a minute or two, which is plenty for switching themes.
+fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> SunTimes {
+	fn sin(degrees: f64) -> f64 {
+		degrees.to_radians().sin()
+	}
+
+	// Days since the J2000 epoch, and the mean solar time at the given longitude
+	let j2000 = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+	let days = (date - j2000).num_days() as f64;
+	let mean_solar_time = days - longitude / 360.0;
+
+	let mean_anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+	let center = 1.9148 * sin(mean_anomaly)
+		+ 0.02 * sin(2.0 * mean_anomaly)
+		+ 0.0003 * sin(3.0 * mean_anomaly);
+	let ecliptic_longitude = (mean_anomaly + center + 180.0 + 102.9372).rem_euclid(360.0);
+	let transit = 2451545.0 + mean_solar_time + 0.0053 * sin(mean_anomaly)
+		- 0.0069 * sin(2.0 * ecliptic_longitude);
+
+	let declination_sin = sin(ecliptic_longitude) * sin(23.4397);
+	let declination_cos = declination_sin.asin().cos();
+
+	// -0.833° accounts for atmospheric refraction and the size of the sun
+	let hour_angle_cos = (sin(-0.833) - sin(latitude) * declination_sin)
+		/ (latitude.to_radians().cos() * declination_cos);
+	if hour_angle_cos > 1.0 {
+		return SunTimes::AlwaysDown;
+	}
+	if hour_angle_cos < -1.0 {
+		return SunTimes::AlwaysUp;
+	}
+	let hour_angle = hour_angle_cos.acos().to_degrees();
+
+	let from_julian = |julian_date: f64| {
+		let timestamp = (julian_date - 2440587.5) * 86400.0;
+		DateTime::from_timestamp(timestamp.round() as i64, 0).unwrap()
+	};
+
+	SunTimes::Rises {
+		sunrise: from_julian(transit - hour_angle / 360.0),
+		sunset: from_julian(transit + hour_angle / 360.0)
+	}
+}
+
+/// The transitions of the schedule on `date`, in the order they happen
+fn transitions_on<Tz: TimeZone>(times: Times, date: NaiveDate, tz: &Tz) -> Vec<Transition<Tz>> {
+	// Times that are skipped by a daylight saving time change don't happen on that day
+	let at = |time: NaiveTime, color_scheme: ColorScheme| {
+		let time = date
+			.and_time(time)
+			.and_local_timezone(tz.clone())
+			.earliest()?;
+		Some(Transition { time, color_scheme })
+	};
+
+	let mut transitions: Vec<Transition<Tz>> = match times {
+		Times::Fixed { light, dark } => {
+			[at(light, ColorScheme::Light), at(dark, ColorScheme::Dark)]
+				.into_iter()
+				.flatten()
+				.collect()
+		}
+		Times::Solar {
+			latitude,
+			longitude
+		} => match sun_times(date, latitude, longitude) {
+			SunTimes::Rises { sunrise, sunset } => vec![
+				Transition {
+					time: sunrise.with_timezone(tz),
+					color_scheme: ColorScheme::Light
+				},
+				Transition {
+					time: sunset.with_timezone(tz),
+					color_scheme: ColorScheme::Dark
+				}
+			],
+			SunTimes::AlwaysUp => at(NaiveTime::MIN, ColorScheme::Light).into_iter().collect(),
+			SunTimes::AlwaysDown => at(NaiveTime::MIN, ColorScheme::Dark).into_iter().collect()
+		}
+	};
+
+	transitions.sort_by(|a, b| a.time.cmp(&b.time));
+	transitions
+}
+
+/// The transitions of `schedule` on `date`, in the order they happen
+pub fn transitions<Tz: TimeZone>(
+	schedule: &Schedule,
+	date: NaiveDate,
+	tz: &Tz
+) -> Result<Vec<Transition<Tz>>, Error> {
+	Ok(transitions_on(Times::new(schedule)?, date, tz))
+}
+
+/// Finds the color scheme `schedule` selects at `now`, and when it changes next
+pub fn state_at<Tz: TimeZone>(
+	schedule: &Schedule,
+	now: &DateTime<Tz>
+) -> Result<ScheduleState<Tz>, Error> {
+	let times = Times::new(schedule)?;
+	let tz = now.timezone();
+	let yesterday = now.date_naive() - Days::new(1);
+
+	let mut color_scheme = None;
+	for offset in 0..=MAX_DAYS_AHEAD + 1 {
+		for transition in transitions_on(times, yesterday + Days::new(offset), &tz) {
+			if transition.time <= *now {
+				color_scheme = Some(transition.color_scheme);
+				continue;
+			}
+
+			let current = match &color_scheme {
+				Some(current) if *current == transition.color_scheme => continue,
+				Some(current) => current.clone(),
+				// Without any earlier transitions, the schedule must have used the other color
+				// scheme before this one
+				None => match transition.color_scheme {
+					ColorScheme::Light => ColorScheme::Dark,
+					ColorScheme::Dark => ColorScheme::Light
+				}
+			};
+			return Ok(ScheduleState {
+				color_scheme: current,
+				next_transition: Some(transition.time)
+			});
+		}
+	}
+
+	Ok(ScheduleState {
+		color_scheme: color_scheme.unwrap_or(ColorScheme::Dark),
+		next_transition: None
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use chrono::FixedOffset;
+
+	use super::*;
+
+	fn fixed(light_at: &str, dark_at: &str) -> Schedule {
+		Schedule {
+			light: "light".to_string(),
+			dark: "dark".to_string(),
+			light_at: Some(light_at.to_string()),
+			dark_at: Some(dark_at.to_string()),
+			latitude: None,
+			longitude: None
+		}
+	}
+
+	fn utc(time: &str) -> DateTime<Utc> {
+		DateTime::parse_from_rfc3339(time).unwrap().to_utc()
+	}
+
+	#[test]
+	fn should_follow_fixed_times() {
+		let schedule = fixed("07:00", "19:00");
+
+		let state = state_at(&schedule, &utc("2024-03-10T12:00:00Z")).unwrap();
+		assert_eq!(state.color_scheme, ColorScheme::Light);
+		assert_eq!(state.next_transition, Some(utc("2024-03-10T19:00:00Z")));
+
+		let state = state_at(&schedule, &utc("2024-03-10T02:00:00Z")).unwrap();
+		assert_eq!(state.color_scheme, ColorScheme::Dark);
+		assert_eq!(state.next_transition, Some(utc("2024-03-10T07:00:00Z")));
+
+		// The light theme may also be used overnight
+		let state = state_at(&fixed("22:00", "06:00"), &utc("2024-03-10T23:00:00Z")).unwrap();
+		assert_eq!(state.color_scheme, ColorScheme::Light);
+		assert_eq!(state.next_transition, Some(utc("2024-03-11T06:00:00Z")));
+	}
+
+	#[test]
+	fn should_calculate_sunrise_and_sunset() {
+		// Berlin on the summer solstice: sunrise at 04:43 and sunset at 21:33 local time
+		let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+		let SunTimes::Rises { sunrise, sunset } = sun_times(date, 52.52, 13.405) else {
+			panic!("The sun should rise in Berlin");
+		};
+
+		let berlin = FixedOffset::east_opt(2 * 3600).unwrap();
+		let minutes = |time: DateTime<Utc>, expected: &str| {
+			let expected = NaiveTime::parse_from_str(expected, "%H:%M").unwrap();
+			(time.with_timezone(&berlin).time() - expected)
+				.num_minutes()
+				.abs()
+		};
+		assert!(minutes(sunrise, "04:43") <= 2, "sunrise at {sunrise}");
+		assert!(minutes(sunset, "21:33") <= 2, "sunset at {sunset}");
+	}
+
+	#[test]
+	fn should_handle_polar_day_and_night() {
+		let summer = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+		let winter = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+
+		assert_eq!(sun_times(summer, 78.22, 15.65), SunTimes::AlwaysUp);
+		assert_eq!(sun_times(winter, 78.22, 15.65), SunTimes::AlwaysDown);
+	}
+
+	#[test]
+	fn should_reject_invalid_schedules() {
+		let mut schedule = fixed("07:00", "19:00");
+		schedule.latitude = Some(52.52);
+		schedule.longitude = Some(13.405);
+		assert!(matches!(
+			Times::new(&schedule),
+			Err(Error::ConflictingTimes)
+		));
+
+		schedule.light_at = None;
+		schedule.dark_at = None;
+		schedule.longitude = None;
+		assert!(matches!(Times::new(&schedule), Err(Error::MissingTimes)));
+
+		assert!(matches!(
+			Times::new(&fixed("7 am", "19:00")),
+			Err(Error::InvalidTime(..))
+		));
+		assert!(matches!(
+			Times::new(&fixed("07:00", "07:00")),
+			Err(Error::SameTimes(_))
+		));
+	}
+}
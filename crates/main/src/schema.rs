@@ -0,0 +1,179 @@
+use std::mem;
+
+use schemars::{
+	gen::{SchemaGenerator, SchemaSettings},
+	schema::{RootSchema, Schema},
+	JsonSchema
+};
+use serde_json::json;
+
+use crate::{
+	config::{Config, DisableReloads, ModuleConfigValue, Theme},
+	theme::derive,
+	types::color::Color
+};
+
+pub struct ModuleOptions {
+	pub name: String,
+	pub options: toml::Table
+}
+
+fn schema_from_json(value: serde_json::Value) -> Schema {
+	serde_json::from_value(value).expect("Statically defined schemas should be valid")
+}
+
+impl JsonSchema for Color {
+	fn schema_name() -> String {
+		"Color".to_string()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		schema_from_json(json!({
+			"type": "string",
+			"description": "A color in #RGB, #RRGGBB or #RRGGBBAA notation",
+			"pattern": "^#([0-9a-fA-F]{3}|[0-9a-fA-F]{6}|[0-9a-fA-F]{8})$"
+		}))
+	}
+}
+
+impl JsonSchema for DisableReloads {
+	fn schema_name() -> String {
+		"DisableReloads".to_string()
+	}
+
+	fn json_schema(_: &mut SchemaGenerator) -> Schema {
+		schema_from_json(json!({
+			"anyOf": [
+				{ "type": "string", "enum": ["none", "all"] },
+				{ "type": "array", "items": { "type": "string" } }
+			]
+		}))
+	}
+}
+
+fn global_options() -> serde_json::Map<String, serde_json::Value> {
+	let options = json!({
+		"font_family": {
+			"type": "string",
+			"description": "The font family to use for UI"
+		},
+		"font_scale": {
+			"type": "number",
+			"description": "A scaling factor for text"
+		},
+		"cursor_theme": {
+			"type": "string",
+			"description": "The cursor theme to use"
+		},
+		"cursor_size": {
+			"type": "integer",
+			"description": "The cursor size to use"
+		},
+		"colors": {
+			"description": "Replaces colors of the theme for modules. Maps color roles like \
+							`ui.border` to color expressions like `terminal.blue` or \
+							`lighten(ui.surface, 0.1)`",
+			"type": "object",
+			"additionalProperties": { "type": "string" }
+		},
+		"wallpaper": {
+			"description": "The path to a wallpaper, or a map from theme names to wallpapers \
+							with an optional `default` key",
+			"anyOf": [
+				{ "type": "string" },
+				{ "type": "object", "additionalProperties": { "type": "string" } }
+			]
+		}
+	});
+	options.as_object().unwrap().clone()
+}
+
+fn module_section_schema(
+	options: serde_json::Map<String, serde_json::Value>,
+	value_schema: &Schema
+) -> Schema {
+	let mut properties = global_options();
+	properties.extend(options);
+
+	schema_from_json(json!({
+		"type": "object",
+		"properties": properties,
+		"additionalProperties": value_schema
+	}))
+}
+
+pub fn config_schema(modules: &[ModuleOptions]) -> RootSchema {
+	let mut generator = SchemaSettings::draft07().into_generator();
+	let mut root = generator.root_schema_for::<Config>();
+	let value_schema = generator.subschema_for::<ModuleConfigValue>();
+	root.definitions.extend(generator.take_definitions());
+
+	let module_names: Vec<&str> = modules.iter().map(|m| m.name.as_str()).collect();
+
+	let properties = &mut root.schema.object().properties;
+	properties.insert(
+		"modules".to_string(),
+		schema_from_json(json!({
+			"description": "The names of the modules to activate",
+			"type": "array",
+			"items": { "type": "string", "enum": module_names }
+		}))
+	);
+	properties.insert(
+		"global".to_string(),
+		module_section_schema(serde_json::Map::new(), &value_schema)
+	);
+	for module in modules {
+		let options = serde_json::to_value(&module.options)
+			.ok()
+			.and_then(|v| v.as_object().cloned())
+			.unwrap_or_default();
+
+		properties.insert(
+			module.name.clone(),
+			module_section_schema(options, &value_schema)
+		);
+	}
+
+	root
+}
+
+pub fn theme_schema() -> RootSchema {
+	let mut root = SchemaSettings::draft07()
+		.into_generator()
+		.into_root_schema_for::<Theme>();
+
+	// Themes that extend another theme may leave out any value, so the required values only
+	// apply to themes without `extends`. Derivable values are never required.
+	let mut section_requirements = serde_json::Map::new();
+	for (section, definition) in [("ui", "UiTheme"), ("terminal", "Terminal")] {
+		let Some(Schema::Object(definition)) = root.definitions.get_mut(definition) else {
+			continue;
+		};
+		let mut required = mem::take(&mut definition.object().required);
+		let derivable: &[&str] = match section {
+			"ui" => &derive::DERIVABLE_UI_KEYS,
+			_ => &derive::DERIVABLE_TERMINAL_KEYS
+		};
+		required.retain(|key| !derivable.contains(&key.as_str()));
+		section_requirements.insert(section.to_string(), json!({ "required": required }));
+	}
+	// Syntax colors that are left out are always derived from the terminal colors
+	if let Some(Schema::Object(definition)) = root.definitions.get_mut("Syntax") {
+		definition.object().required.clear();
+	}
+
+	let mut required = mem::take(&mut root.schema.object().required);
+	required.remove("syntax");
+
+	let subschemas = root.schema.subschemas();
+	subschemas.if_schema = Some(Box::new(schema_from_json(
+		json!({ "not": { "required": ["extends"] } })
+	)));
+	subschemas.then_schema = Some(Box::new(schema_from_json(json!({
+		"required": required,
+		"properties": section_requirements
+	}))));
+
+	root
+}
@@ -0,0 +1,176 @@
+use std::f32::consts::PI;
+
+use super::{
+	derive,
+	generate::{ensure_contrast, hue_difference, SECONDARY_HUE_OFFSET}
+};
+use crate::{
+	config::{Accent, ColorScheme, Theme},
+	types::color::Color,
+	utils::oklch::OklchColor
+};
+
+/// The minimum contrast ratio of the accent against the background, which WCAG 2.x requires
+/// for UI components
+const MIN_ACCENT_CONTRAST: f32 = 3.0;
+/// The minimum contrast ratio of the text on top of the accent (WCAG 2.x level AA)
+const MIN_TEXT_CONTRAST: f32 = 4.5;
+/// Terminal colors with a hue closer than this to the accent are shifted to its hue
+const MAX_SHIFT_HUE_DISTANCE: f32 = PI / 8.0;
+/// Colors with less chroma than this look gray, so their hue is left alone
+const MIN_SHIFT_CHROMA: f32 = 0.04;
+
+/// Replaces the accent colors of `theme` with the configured accent. The accent is made
+/// lighter or darker if it doesn't stand out enough against the background, or if text on
+/// top of it wouldn't be readable.
+pub fn apply(original: &Theme, accent: &Accent) -> Theme {
+	let mut theme = original.clone();
+
+	let direction = match theme.ui.color_scheme {
+		ColorScheme::Dark => 1.0,
+		ColorScheme::Light => -1.0
+	};
+	let mut primary = ensure_contrast(
+		accent.color.into_oklch(),
+		theme.ui.background,
+		MIN_ACCENT_CONTRAST,
+		direction
+	);
+
+	// Text on top of accents of medium lightness may not be readable, whether the text is
+	// light or dark, so these are moved further away from the background
+	while text_contrast(primary) < MIN_TEXT_CONTRAST
+		&& (0.0..=1.0).contains(&(primary.lightness() + 0.02 * direction))
+	{
+		primary = primary.lighten(0.02 * direction);
+	}
+
+	theme.ui.primary = Color::from_oklch(primary, accent.color.a);
+	theme.ui.text_primary = theme.ui.primary.readable_text();
+
+	if accent.secondary {
+		let secondary = primary.with_hue(primary.hue() + SECONDARY_HUE_OFFSET);
+		theme.ui.secondary = Color::from_oklch(secondary, accent.color.a);
+	}
+
+	if accent.shift_terminal {
+		let terminal = &mut theme.terminal;
+		let colors = [
+			&mut terminal.red,
+			&mut terminal.green,
+			&mut terminal.yellow,
+			&mut terminal.blue,
+			&mut terminal.magenta,
+			&mut terminal.cyan,
+			&mut terminal.bright_red,
+			&mut terminal.bright_green,
+			&mut terminal.bright_yellow,
+			&mut terminal.bright_blue,
+			&mut terminal.bright_magenta,
+			&mut terminal.bright_cyan
+		];
+		for color in colors {
+			*color = shift_hue(*color, primary);
+		}
+		terminal.palette_256 = derive::palette_256(terminal);
+
+		// Syntax colors that are the same as the ones derived from the terminal colors follow
+		// them, while the ones the theme sets itself are kept
+		let old = derive::syntax(&original.terminal);
+		let new = derive::syntax(&theme.terminal);
+		let syntax = &mut theme.syntax;
+		let roles = [
+			(&mut syntax.keyword, old.keyword, new.keyword),
+			(&mut syntax.string, old.string, new.string),
+			(&mut syntax.comment, old.comment, new.comment),
+			(&mut syntax.function, old.function, new.function),
+			(&mut syntax.r#type, old.r#type, new.r#type),
+			(&mut syntax.constant, old.constant, new.constant),
+			(&mut syntax.variable, old.variable, new.variable),
+			(&mut syntax.diff_add, old.diff_add, new.diff_add),
+			(&mut syntax.diff_change, old.diff_change, new.diff_change),
+			(&mut syntax.diff_delete, old.diff_delete, new.diff_delete)
+		];
+		for (color, old, new) in roles {
+			if *color == old {
+				*color = new;
+			}
+		}
+	}
+
+	theme
+}
+
+/// The contrast ratio of `color` with the most readable text color on top of it
+fn text_contrast(color: OklchColor) -> f32 {
+	let color = Color::from_oklch(color, 0xff);
+	Color::contrast_ratio(color, color.readable_text())
+}
+
+/// Gives `color` the hue of `accent` if the two are close, keeping its lightness and chroma
+fn shift_hue(color: Color, accent: OklchColor) -> Color {
+	let oklch = color.into_oklch();
+	if oklch.chroma() < MIN_SHIFT_CHROMA
+		|| hue_difference(oklch.hue(), accent.hue()).abs() > MAX_SHIFT_HUE_DISTANCE
+	{
+		return color;
+	}
+
+	Color::from_oklch(oklch.with_hue(accent.hue()), color.a)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::theme::generate::{generate, ThemeSeeds};
+
+	fn theme() -> Theme {
+		generate(&ThemeSeeds {
+			background: "#1e1e2e".parse().unwrap(),
+			primary: "#89b4fa".parse().unwrap(),
+			secondary: None,
+			color_scheme: None,
+			palette: Vec::new()
+		})
+	}
+
+	#[test]
+	fn should_apply_readable_accent() {
+		let theme = theme();
+		let accent = Accent {
+			// Too dark to stand out against the background
+			color: "#3a1f5d".parse().unwrap(),
+			secondary: false,
+			shift_terminal: true
+		};
+
+		let themed = apply(&theme, &accent);
+
+		assert!(Color::contrast_ratio(themed.ui.primary, themed.ui.background) >= 3.0);
+		assert!(Color::contrast_ratio(themed.ui.text_primary, themed.ui.primary) >= 4.5);
+		assert_eq!(themed.ui.secondary, theme.ui.secondary);
+		assert_eq!(themed.terminal.black, theme.terminal.black);
+	}
+
+	#[test]
+	fn should_shift_derived_syntax_colors() {
+		let mut theme = theme();
+		let keyword = "#ff00ff".parse().unwrap();
+		theme.syntax.keyword = keyword;
+		theme.syntax.r#type = theme.terminal.blue;
+		let accent = Accent {
+			color: "#5a8df0".parse().unwrap(),
+			secondary: false,
+			shift_terminal: true
+		};
+
+		let themed = apply(&theme, &accent);
+
+		assert_ne!(themed.terminal.blue, theme.terminal.blue);
+		assert_eq!(themed.syntax.function, themed.terminal.blue);
+		assert_eq!(themed.syntax.string, themed.terminal.green);
+		assert_eq!(themed.syntax.keyword, keyword);
+		// Set by the theme, even though it is the same as a terminal color
+		assert_eq!(themed.syntax.r#type, theme.terminal.blue);
+	}
+}
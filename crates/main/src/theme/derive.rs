@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+
+use oklab::{oklab_to_srgb, srgb_to_oklab, Oklab, Rgb};
+
+use crate::{
+	config::{ColorScheme, Syntax, Terminal},
+	types::color::Color,
+	utils::lerp
+};
+
+/// The `ui` values that may be left out of a theme, in the order they are derived in. Later
+/// values may depend on earlier ones, like `text_success` on `success`.
+pub const DERIVABLE_UI_KEYS: [&str; 11] = [
+	"border",
+	"shadow",
+	"success",
+	"info",
+	"text_background",
+	"text_surface",
+	"text_primary",
+	"text_success",
+	"text_info",
+	"text_warning",
+	"text_error"
+];
+
+/// The `terminal` values that may be left out of a theme, in the order they are derived in
+pub const DERIVABLE_TERMINAL_KEYS: [&str; 6] = [
+	"foreground",
+	"background",
+	"cursor",
+	"cursor_text",
+	"selection_background",
+	"selection_foreground"
+];
+
+/// The `palette` colors that every theme has, so that modules can rely on them. They are
+/// taken from the terminal colors unless the theme defines them.
+pub const DEFAULT_PALETTE_KEYS: [&str; 7] = [
+	"red", "orange", "yellow", "green", "cyan", "blue", "magenta"
+];
+
+/// A border color that is slightly more prominent than the background
+pub fn border(background: Color, color_scheme: &ColorScheme) -> Color {
+	let background = background.into_oklch();
+	let border = match color_scheme {
+		ColorScheme::Dark => background.lighten(0.16),
+		ColorScheme::Light => background.darken(0.16)
+	};
+	Color::from_oklch(border, 0xff)
+}
+
+/// A translucent shadow color that is tinted with the hue of the background
+pub fn shadow(background: Color, color_scheme: &ColorScheme) -> Color {
+	let background = background.into_oklch();
+	match color_scheme {
+		ColorScheme::Dark => {
+			Color::from_oklch(background.shade(background.lightness() * 0.5), 0x80)
+		}
+		ColorScheme::Light => Color::from_oklch(background.shade(0.2), 0x40)
+	}
+}
+
+/// Syntax colors in the style of most terminal color schemes
+pub fn syntax(terminal: &Terminal) -> Syntax {
+	Syntax {
+		keyword: terminal.magenta,
+		string: terminal.green,
+		comment: terminal.bright_black,
+		function: terminal.blue,
+		r#type: terminal.yellow,
+		constant: terminal.cyan,
+		variable: terminal.white,
+		diff_add: terminal.green,
+		diff_change: terminal.yellow,
+		diff_delete: terminal.red,
+		extra: BTreeMap::new()
+	}
+}
+
+fn get_color(table: &toml::Table, key: &str) -> Option<Color> {
+	table.get(key)?.as_str()?.parse().ok()
+}
+
+fn derive_ui_value(key: &str, ui: &toml::Table, terminal: Option<&toml::Table>) -> Option<Color> {
+	let color_scheme =
+		|| -> Option<ColorScheme> { ui.get("color_scheme")?.clone().try_into().ok() };
+
+	match key {
+		"border" => Some(border(get_color(ui, "background")?, &color_scheme()?)),
+		"shadow" => Some(shadow(get_color(ui, "background")?, &color_scheme()?)),
+		"success" => get_color(terminal?, "green"),
+		"info" => get_color(terminal?, "blue"),
+		_ => {
+			let base = key.strip_prefix("text_")?;
+			Some(get_color(ui, base)?.readable_text())
+		}
+	}
+}
+
+fn derive_terminal_value(key: &str, terminal: &toml::Table, ui: &toml::Table) -> Option<Color> {
+	match key {
+		"foreground" => get_color(ui, "text_background"),
+		"background" => get_color(ui, "background"),
+		"cursor" => get_color(terminal, "foreground"),
+		"cursor_text" => get_color(terminal, "background"),
+		"selection_background" => get_color(ui, "surface"),
+		"selection_foreground" => get_color(ui, "text_surface"),
+		_ => None
+	}
+}
+
+fn derive_palette_value(key: &str, terminal: &toml::Table) -> Option<Color> {
+	match key {
+		"orange" => Some(Color::mix(
+			get_color(terminal, "red")?,
+			get_color(terminal, "yellow")?
+		)),
+		_ => get_color(terminal, key)
+	}
+}
+
+/// Inserts the `keys` that are missing from `section`, in order, and adds them to `derived`.
+/// `derive` is called with the section as it is at that point, so later keys can depend on
+/// earlier ones.
+fn fill_section(
+	section: &mut toml::Table,
+	name: &str,
+	keys: &[&str],
+	derive: impl Fn(&str, &toml::Table) -> Option<Color>,
+	derived: &mut Vec<String>
+) {
+	for key in keys {
+		if section.contains_key(*key) {
+			continue;
+		}
+
+		let Some(value) = derive(key, section) else {
+			continue;
+		};
+		section.insert(key.to_string(), toml::Value::String(value.to_string()));
+		derived.push(format!("{name}.{key}"));
+	}
+}
+
+fn get_section<'a>(table: &'a mut toml::Table, name: &str) -> Option<&'a mut toml::Table> {
+	table.get_mut(name).and_then(toml::Value::as_table_mut)
+}
+
+/// Fills in the values that are missing from a theme table but can be derived from other
+/// values, and returns the keys of the values that were derived. Values whose inputs are
+/// missing or invalid are left out, so that deserializing the theme reports the actual problem.
+pub fn fill_derived(table: &mut toml::Table) -> Vec<String> {
+	let mut derived = Vec::new();
+
+	let terminal = table
+		.get("terminal")
+		.and_then(toml::Value::as_table)
+		.cloned();
+	if let Some(ui) = get_section(table, "ui") {
+		fill_section(
+			ui,
+			"ui",
+			&DERIVABLE_UI_KEYS,
+			|key, ui| derive_ui_value(key, ui, terminal.as_ref()),
+			&mut derived
+		);
+	}
+
+	let ui = table
+		.get("ui")
+		.and_then(toml::Value::as_table)
+		.cloned()
+		.unwrap_or_default();
+	let Some(terminal) = get_section(table, "terminal") else {
+		return derived;
+	};
+	fill_section(
+		terminal,
+		"terminal",
+		&DERIVABLE_TERMINAL_KEYS,
+		|key, terminal| derive_terminal_value(key, terminal, &ui),
+		&mut derived
+	);
+	let terminal = terminal.clone();
+
+	// Unlike `ui` and `terminal`, these sections are optional
+	for name in ["palette", "syntax"] {
+		table
+			.entry(name)
+			.or_insert_with(|| toml::Value::Table(toml::Table::new()));
+	}
+
+	if let Some(palette) = get_section(table, "palette") {
+		fill_section(
+			palette,
+			"palette",
+			&DEFAULT_PALETTE_KEYS,
+			|key, _| derive_palette_value(key, &terminal),
+			&mut derived
+		);
+	}
+
+	let Ok(terminal) = toml::Value::Table(terminal).try_into::<Terminal>() else {
+		return derived;
+	};
+	let syntax = syntax(&terminal).colors();
+	if let Some(section) = get_section(table, "syntax") {
+		let keys = syntax.map(|(key, _)| key);
+		fill_section(
+			section,
+			"syntax",
+			&keys,
+			|key, _| {
+				syntax
+					.iter()
+					.find(|(name, _)| *name == key)
+					.map(|(_, color)| *color)
+			},
+			&mut derived
+		);
+	}
+
+	derived
+}
+
+/// Interpolates between `col1` and `col2` in the OKLab color space
+fn lerp_oklab(col1: Oklab, col2: Oklab, t: f32) -> Oklab {
+	Oklab {
+		l: lerp(col1.l, col2.l, t),
+		a: lerp(col1.a, col2.a, t),
+		b: lerp(col1.b, col2.b, t)
+	}
+}
+
+/// The 256 colors of the extended terminal palette. The 6x6x6 color cube is interpolated
+/// between the background, the six accent colors and the foreground, which are placed at the
+/// corners of the cube where black, the primary and secondary colors and white would be. The
+/// grayscale ramp is interpolated between the background and the foreground.
+pub fn palette_256(terminal: &Terminal) -> Vec<Color> {
+	let oklab = |color: Color| {
+		srgb_to_oklab(Rgb {
+			r: color.r,
+			g: color.g,
+			b: color.b
+		})
+	};
+	let color = |lab: Oklab| {
+		let rgb = oklab_to_srgb(lab);
+		Color::new_rgba(rgb.r, rgb.g, rgb.b, 0xff)
+	};
+
+	let background = oklab(terminal.background);
+	let foreground = oklab(terminal.foreground);
+	let [red, green, yellow, blue, magenta, cyan] = [
+		terminal.red,
+		terminal.green,
+		terminal.yellow,
+		terminal.blue,
+		terminal.magenta,
+		terminal.cyan
+	]
+	.map(oklab);
+
+	let mut palette: Vec<Color> = terminal.colors().iter().map(|(_, color)| *color).collect();
+
+	for r in 0..6 {
+		let r = r as f32 / 5.0;
+		let corners = [
+			lerp_oklab(background, red, r),
+			lerp_oklab(green, yellow, r),
+			lerp_oklab(blue, magenta, r),
+			lerp_oklab(cyan, foreground, r)
+		];
+		for g in 0..6 {
+			let g = g as f32 / 5.0;
+			let without_blue = lerp_oklab(corners[0], corners[1], g);
+			let with_blue = lerp_oklab(corners[2], corners[3], g);
+			for b in 0..6 {
+				palette.push(color(lerp_oklab(without_blue, with_blue, b as f32 / 5.0)));
+			}
+		}
+	}
+
+	for i in 0..24 {
+		palette.push(color(lerp_oklab(
+			background,
+			foreground,
+			(i + 1) as f32 / 25.0
+		)));
+	}
+
+	palette
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_only_derive_missing_values() {
+		let mut table: toml::Table = toml::from_str(
+			"[ui]\ncolor_scheme = \"dark\"\nbackground = \"#282828\"\nprimary = \
+			 \"#458588\"\nsuccess = \"#98971a\"\n[terminal]\nblue = \"#83a598\""
+		)
+		.unwrap();
+
+		let derived = fill_derived(&mut table);
+
+		assert_eq!(table["ui"]["success"].as_str(), Some("#98971a"));
+		assert_eq!(table["ui"]["info"].as_str(), Some("#83a598ff"));
+		assert!(derived.contains(&"ui.text_success".to_string()));
+		assert!(!derived.contains(&"ui.success".to_string()));
+		// `surface` isn't set, so there's nothing to derive its text color from
+		assert!(!derived.contains(&"ui.text_surface".to_string()));
+	}
+
+	#[test]
+	fn should_keep_palette_colors_of_the_theme() {
+		let mut table: toml::Table = toml::from_str(
+			"[terminal]\nred = \"#cc241d\"\nyellow = \"#d79921\"\nblue = \
+			 \"#458588\"\n[palette]\nblue = \"#83a598\"\nbrown = \"#a0522d\""
+		)
+		.unwrap();
+
+		let derived = fill_derived(&mut table);
+
+		assert_eq!(table["palette"]["blue"].as_str(), Some("#83a598"));
+		assert_eq!(table["palette"]["brown"].as_str(), Some("#a0522d"));
+		assert_eq!(table["palette"]["red"].as_str(), Some("#cc241dff"));
+		assert!(derived.contains(&"palette.orange".to_string()));
+		// There is no `terminal.green` to take the default from
+		assert!(!table["palette"].as_table().unwrap().contains_key("green"));
+	}
+
+	#[test]
+	fn should_derive_syntax_from_terminal_colors() {
+		let path = concat!(
+			env!("CARGO_MANIFEST_DIR"),
+			"/../../assets/themes/gruvbox/theme.toml"
+		);
+		let mut table: toml::Table =
+			toml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+		table
+			.entry("syntax")
+			.or_insert_with(|| toml::Value::Table(toml::Table::new()))
+			.as_table_mut()
+			.unwrap()
+			.insert(
+				"comment".to_string(),
+				toml::Value::String("#7c6f64".to_string())
+			);
+
+		let derived = fill_derived(&mut table);
+
+		assert_eq!(table["syntax"]["keyword"].as_str(), Some("#b16286ff"));
+		assert_eq!(table["syntax"]["comment"].as_str(), Some("#7c6f64"));
+		assert!(derived.contains(&"syntax.keyword".to_string()));
+		assert!(!derived.contains(&"syntax.comment".to_string()));
+	}
+
+	#[test]
+	fn should_place_terminal_colors_at_the_corners_of_the_color_cube() {
+		let path = concat!(
+			env!("CARGO_MANIFEST_DIR"),
+			"/../../assets/themes/gruvbox/theme.toml"
+		);
+		let mut table: toml::Table =
+			toml::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+		fill_derived(&mut table);
+		let terminal: Terminal = table["terminal"].clone().try_into().unwrap();
+
+		let palette = palette_256(&terminal);
+
+		let assert_close = |actual: Color, expected: Color| {
+			let channels = |c: Color| [c.r as i16, c.g as i16, c.b as i16];
+			for (a, e) in channels(actual).iter().zip(channels(expected)) {
+				assert!((a - e).abs() <= 1, "expected {expected}, got {actual}");
+			}
+		};
+		assert_eq!(palette.len(), 256);
+		assert_eq!(palette[1], terminal.red);
+		assert_close(palette[16], terminal.background);
+		assert_close(palette[16 + 36 * 5], terminal.red);
+		assert_close(palette[16 + 6 * 5 + 5], terminal.cyan);
+		assert_close(palette[231], terminal.foreground);
+	}
+}
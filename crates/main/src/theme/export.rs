@@ -0,0 +1,507 @@
+use serde_json::{json, Map, Value};
+
+use crate::{config::Theme, types::color::Color};
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+	Base16,
+	Xresources,
+	Kitty,
+	Alacritty,
+	Foot,
+	Gimp,
+	Css,
+	Scss,
+	Json
+}
+
+/// Formats a color as `#RRGGBB`, for formats that don't support transparency
+fn hex(color: Color) -> String {
+	format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+/// Formats a color as `#RRGGBB`, or `#RRGGBBAA` if it is translucent
+fn hex_with_alpha(color: Color) -> String {
+	if color.a == 0xff {
+		hex(color)
+	} else {
+		color.to_string()
+	}
+}
+
+/// All colors of the theme, with names like `ui-background` or `terminal-bright-red`
+fn named_colors(theme: &Theme) -> Vec<(String, Color)> {
+	let ui = theme
+		.ui
+		.colors()
+		.into_iter()
+		.map(|(name, color)| (format!("ui-{name}"), color));
+	let terminal = theme
+		.terminal
+		.colors()
+		.into_iter()
+		.map(|(name, color)| (format!("terminal-{name}"), color));
+
+	ui.chain(terminal)
+		.map(|(name, color)| (name.replace('_', "-"), color))
+		.collect()
+}
+
+/// Renders `theme` in the given format, without applying it anywhere
+pub fn export(theme: &Theme, name: &str, format: ExportFormat) -> String {
+	let lines = match format {
+		ExportFormat::Base16 => base16(theme, name),
+		ExportFormat::Xresources => xresources(theme, name),
+		ExportFormat::Kitty => kitty(theme, name),
+		ExportFormat::Alacritty => alacritty(theme, name),
+		ExportFormat::Foot => foot(theme, name),
+		ExportFormat::Gimp => gimp(theme, name),
+		ExportFormat::Css => css(theme, name),
+		ExportFormat::Scss => scss(theme, name),
+		ExportFormat::Json => return format!("{:#}\n", json(theme, name))
+	};
+
+	let mut output = lines.join("\n");
+	output.push('\n');
+	output
+}
+
+/// Maps the theme onto the 16 base16 colors, using the same mapping as the base16 importer.
+/// The colors base16 has no equivalent for are mixed from the ones that are closest.
+fn base16(theme: &Theme, name: &str) -> Vec<String> {
+	let ui = &theme.ui;
+	let terminal = &theme.terminal;
+	let orange = Color::mix(terminal.red, terminal.yellow);
+
+	let palette = [
+		ui.background,
+		ui.surface,
+		ui.border,
+		terminal.bright_black,
+		Color::mix(terminal.bright_black, ui.text_background),
+		ui.text_background,
+		Color::mix(ui.text_background, terminal.bright_white),
+		terminal.bright_white,
+		terminal.red,
+		orange,
+		terminal.yellow,
+		terminal.green,
+		terminal.cyan,
+		terminal.blue,
+		terminal.magenta,
+		orange.darken(0.1)
+	];
+
+	let mut lines = vec![
+		"system: \"base16\"".to_string(),
+		format!("name: \"{name}\""),
+		"author: \"\"".to_string(),
+		format!("variant: \"{}\"", ui.color_scheme),
+		"palette:".to_string()
+	];
+	lines.extend(
+		palette
+			.iter()
+			.enumerate()
+			.map(|(i, color)| format!("  base{i:02X}: \"{}\"", hex(*color)))
+	);
+	lines
+}
+
+fn xresources(theme: &Theme, name: &str) -> Vec<String> {
+	let mut lines = vec![
+		format!("! {name}, exported from niji"),
+		format!("*.foreground: {}", hex(theme.terminal.foreground)),
+		format!("*.background: {}", hex(theme.terminal.background)),
+		format!("*.cursorColor: {}", hex(theme.terminal.cursor)),
+		format!(
+			"*.highlightColor: {}",
+			hex(theme.terminal.selection_background)
+		),
+		format!(
+			"*.highlightTextColor: {}",
+			hex(theme.terminal.selection_foreground)
+		)
+	];
+	lines.extend(
+		theme
+			.terminal
+			.colors()
+			.iter()
+			.enumerate()
+			.map(|(i, (_, color))| format!("*.color{i}: {}", hex(*color)))
+	);
+	lines
+}
+
+fn kitty(theme: &Theme, name: &str) -> Vec<String> {
+	let mut lines = vec![
+		format!("## name: {name}"),
+		String::new(),
+		format!("foreground {}", hex(theme.terminal.foreground)),
+		format!("background {}", hex(theme.terminal.background)),
+		format!(
+			"selection_foreground {}",
+			hex(theme.terminal.selection_foreground)
+		),
+		format!(
+			"selection_background {}",
+			hex(theme.terminal.selection_background)
+		),
+		format!("cursor {}", hex(theme.terminal.cursor)),
+		format!("cursor_text_color {}", hex(theme.terminal.cursor_text)),
+		String::new()
+	];
+	lines.extend(
+		theme
+			.terminal
+			.colors()
+			.iter()
+			.enumerate()
+			.map(|(i, (_, color))| format!("color{i} {}", hex(*color)))
+	);
+	lines
+}
+
+fn alacritty(theme: &Theme, name: &str) -> Vec<String> {
+	let colors = theme.terminal.colors();
+
+	let mut lines = vec![
+		format!("# {name}, exported from niji"),
+		String::new(),
+		"[colors.primary]".to_string(),
+		format!("background = \"{}\"", hex(theme.terminal.background)),
+		format!("foreground = \"{}\"", hex(theme.terminal.foreground)),
+		String::new(),
+		"[colors.cursor]".to_string(),
+		format!("cursor = \"{}\"", hex(theme.terminal.cursor)),
+		format!("text = \"{}\"", hex(theme.terminal.cursor_text)),
+		String::new(),
+		"[colors.selection]".to_string(),
+		format!(
+			"background = \"{}\"",
+			hex(theme.terminal.selection_background)
+		),
+		format!("text = \"{}\"", hex(theme.terminal.selection_foreground))
+	];
+	for (section, colors) in [("normal", &colors[..8]), ("bright", &colors[8..])] {
+		lines.push(String::new());
+		lines.push(format!("[colors.{section}]"));
+		lines.extend(colors.iter().map(|(name, color)| {
+			let name = name.trim_start_matches("bright_");
+			format!("{name} = \"{}\"", hex(*color))
+		}));
+	}
+	lines
+}
+
+fn foot(theme: &Theme, name: &str) -> Vec<String> {
+	// foot expects colors without a leading `#`
+	let color = |color: Color| hex(color)[1..].to_string();
+
+	let mut lines = vec![
+		format!("# {name}, exported from niji"),
+		String::new(),
+		"[colors]".to_string(),
+		format!("foreground={}", color(theme.terminal.foreground)),
+		format!("background={}", color(theme.terminal.background)),
+		format!(
+			"cursor={} {}",
+			color(theme.terminal.cursor_text),
+			color(theme.terminal.cursor)
+		),
+		format!(
+			"selection-foreground={}",
+			color(theme.terminal.selection_foreground)
+		),
+		format!(
+			"selection-background={}",
+			color(theme.terminal.selection_background)
+		)
+	];
+	for (i, (_, terminal_color)) in theme.terminal.colors().iter().enumerate() {
+		let key = if i < 8 { "regular" } else { "bright" };
+		lines.push(format!("{key}{}={}", i % 8, color(*terminal_color)));
+	}
+	lines
+}
+
+fn gimp(theme: &Theme, name: &str) -> Vec<String> {
+	let mut lines = vec![
+		"GIMP Palette".to_string(),
+		format!("Name: {name}"),
+		"Columns: 8".to_string(),
+		"#".to_string()
+	];
+	lines.extend(
+		named_colors(theme)
+			.into_iter()
+			.map(|(name, color)| format!("{:3} {:3} {:3}\t{name}", color.r, color.g, color.b))
+	);
+	lines
+}
+
+fn css(theme: &Theme, name: &str) -> Vec<String> {
+	let mut lines = vec![
+		format!("/* {name}, exported from niji */"),
+		":root {".to_string()
+	];
+	lines.extend(
+		named_colors(theme)
+			.into_iter()
+			.map(|(name, color)| format!("\t--{name}: {};", hex_with_alpha(color)))
+	);
+	lines.push("}".to_string());
+	lines
+}
+
+fn scss(theme: &Theme, name: &str) -> Vec<String> {
+	let mut lines = vec![format!("// {name}, exported from niji")];
+	lines.extend(
+		named_colors(theme)
+			.into_iter()
+			.map(|(name, color)| format!("${name}: {};", hex_with_alpha(color)))
+	);
+	lines
+}
+
+fn json(theme: &Theme, name: &str) -> Value {
+	let section = |colors: &[(&str, Color)]| {
+		colors
+			.iter()
+			.map(|(name, color)| (name.to_string(), Value::String(hex_with_alpha(*color))))
+			.collect::<Map<_, _>>()
+	};
+
+	json!({
+		"name": name,
+		"color_scheme": theme.ui.color_scheme.to_string(),
+		"ui": section(&theme.ui.colors()),
+		"terminal": section(&theme.terminal.colors())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		config::Terminal,
+		theme::generate::{generate, ThemeSeeds}
+	};
+
+	fn color(hex: &str) -> Color {
+		hex.parse().unwrap()
+	}
+
+	fn theme() -> Theme {
+		let mut theme = generate(&ThemeSeeds {
+			background: color("#111111"),
+			primary: color("#5555ff"),
+			secondary: None,
+			color_scheme: None,
+			palette: Vec::new()
+		});
+		theme.ui.background = color("#111111");
+		theme.ui.surface = color("#222222");
+		theme.ui.border = color("#444444");
+		theme.ui.shadow = color("#00000080");
+		theme.ui.text_background = color("#eeeeee");
+		theme.terminal = Terminal {
+			black: color("#000000"),
+			red: color("#aa0000"),
+			green: color("#00aa00"),
+			yellow: color("#aaaa00"),
+			blue: color("#0000aa"),
+			magenta: color("#aa00aa"),
+			cyan: color("#00aaaa"),
+			white: color("#aaaaaa"),
+			bright_black: color("#555555"),
+			bright_red: color("#ff5555"),
+			bright_green: color("#55ff55"),
+			bright_yellow: color("#ffff55"),
+			bright_blue: color("#5555ff"),
+			bright_magenta: color("#ff55ff"),
+			bright_cyan: color("#55ffff"),
+			bright_white: color("#ffffff"),
+			foreground: color("#eeeeee"),
+			background: color("#111111"),
+			cursor: color("#dddddd"),
+			cursor_text: color("#222222"),
+			selection_background: color("#333333"),
+			selection_foreground: color("#cccccc"),
+			palette_256: Vec::new()
+		};
+		theme
+	}
+
+	#[test]
+	fn should_export_kitty() {
+		let expected = [
+			"## name: test",
+			"",
+			"foreground #eeeeee",
+			"background #111111",
+			"selection_foreground #cccccc",
+			"selection_background #333333",
+			"cursor #dddddd",
+			"cursor_text_color #222222",
+			"",
+			"color0 #000000",
+			"color1 #aa0000",
+			"color2 #00aa00",
+			"color3 #aaaa00",
+			"color4 #0000aa",
+			"color5 #aa00aa",
+			"color6 #00aaaa",
+			"color7 #aaaaaa",
+			"color8 #555555",
+			"color9 #ff5555",
+			"color10 #55ff55",
+			"color11 #ffff55",
+			"color12 #5555ff",
+			"color13 #ff55ff",
+			"color14 #55ffff",
+			"color15 #ffffff",
+			""
+		]
+		.join("\n");
+
+		assert_eq!(export(&theme(), "test", ExportFormat::Kitty), expected);
+	}
+
+	#[test]
+	fn should_export_foot() {
+		let expected = [
+			"# test, exported from niji",
+			"",
+			"[colors]",
+			"foreground=eeeeee",
+			"background=111111",
+			"cursor=222222 dddddd",
+			"selection-foreground=cccccc",
+			"selection-background=333333",
+			"regular0=000000",
+			"regular1=aa0000",
+			"regular2=00aa00",
+			"regular3=aaaa00",
+			"regular4=0000aa",
+			"regular5=aa00aa",
+			"regular6=00aaaa",
+			"regular7=aaaaaa",
+			"bright0=555555",
+			"bright1=ff5555",
+			"bright2=55ff55",
+			"bright3=ffff55",
+			"bright4=5555ff",
+			"bright5=ff55ff",
+			"bright6=55ffff",
+			"bright7=ffffff",
+			""
+		]
+		.join("\n");
+
+		assert_eq!(export(&theme(), "test", ExportFormat::Foot), expected);
+	}
+
+	#[test]
+	fn should_export_alacritty() {
+		let expected = [
+			"# test, exported from niji",
+			"",
+			"[colors.primary]",
+			"background = \"#111111\"",
+			"foreground = \"#eeeeee\"",
+			"",
+			"[colors.cursor]",
+			"cursor = \"#dddddd\"",
+			"text = \"#222222\"",
+			"",
+			"[colors.selection]",
+			"background = \"#333333\"",
+			"text = \"#cccccc\"",
+			"",
+			"[colors.normal]",
+			"black = \"#000000\"",
+			"red = \"#aa0000\"",
+			"green = \"#00aa00\"",
+			"yellow = \"#aaaa00\"",
+			"blue = \"#0000aa\"",
+			"magenta = \"#aa00aa\"",
+			"cyan = \"#00aaaa\"",
+			"white = \"#aaaaaa\"",
+			"",
+			"[colors.bright]",
+			"black = \"#555555\"",
+			"red = \"#ff5555\"",
+			"green = \"#55ff55\"",
+			"yellow = \"#ffff55\"",
+			"blue = \"#5555ff\"",
+			"magenta = \"#ff55ff\"",
+			"cyan = \"#55ffff\"",
+			"white = \"#ffffff\"",
+			""
+		]
+		.join("\n");
+
+		assert_eq!(export(&theme(), "test", ExportFormat::Alacritty), expected);
+	}
+
+	#[test]
+	fn should_export_base16() {
+		let expected = [
+			"system: \"base16\"",
+			"name: \"test\"",
+			"author: \"\"",
+			"variant: \"dark\"",
+			"palette:",
+			"  base00: \"#111111\"",
+			"  base01: \"#222222\"",
+			"  base02: \"#444444\"",
+			"  base03: \"#555555\"",
+			"  base04: \"#9e9e9e\"",
+			"  base05: \"#eeeeee\"",
+			"  base06: \"#f6f6f6\"",
+			"  base07: \"#ffffff\"",
+			"  base08: \"#aa0000\"",
+			"  base09: \"#bc6300\"",
+			"  base0A: \"#aaaa00\"",
+			"  base0B: \"#00aa00\"",
+			"  base0C: \"#00aaaa\"",
+			"  base0D: \"#0000aa\"",
+			"  base0E: \"#aa00aa\"",
+			"  base0F: \"#9b4500\"",
+			""
+		]
+		.join("\n");
+
+		assert_eq!(export(&theme(), "test", ExportFormat::Base16), expected);
+	}
+
+	#[test]
+	fn should_export_named_colors() {
+		let theme = theme();
+
+		let css = export(&theme, "test", ExportFormat::Css);
+		assert!(css.starts_with("/* test, exported from niji */\n:root {\n"));
+		assert!(css.contains("\t--ui-shadow: #00000080;\n"));
+		assert!(css.contains("\t--terminal-bright-red: #ff5555;\n"));
+		assert!(css.ends_with("}\n"));
+
+		let scss = export(&theme, "test", ExportFormat::Scss);
+		assert!(scss.contains("\n$ui-surface: #222222;\n"));
+		assert!(scss.contains("\n$terminal-bright-white: #ffffff;\n"));
+
+		// GIMP palettes have no transparency
+		let gimp = export(&theme, "test", ExportFormat::Gimp);
+		assert!(gimp.starts_with("GIMP Palette\nName: test\nColumns: 8\n#\n"));
+		assert!(gimp.contains("\n  0   0   0\tui-shadow\n"));
+		assert!(gimp.contains("\n255  85  85\tterminal-bright-red\n"));
+
+		let json: Value =
+			serde_json::from_str(&export(&theme, "test", ExportFormat::Json)).unwrap();
+		assert_eq!(json["name"], "test");
+		assert_eq!(json["color_scheme"], "dark");
+		assert_eq!(json["ui"]["shadow"], "#00000080");
+		assert_eq!(json["terminal"]["cyan"], "#00aaaa");
+	}
+}
@@ -0,0 +1,280 @@
+use std::{cmp::Reverse, f32::consts::PI, io, path::Path};
+
+use image::{DynamicImage, ImageReader};
+use oklab::{srgb_to_oklab, Oklab, Rgb};
+use thiserror::Error;
+
+use super::generate::{self, ThemeSeeds};
+use crate::{config::ColorScheme, types::color::Color, utils::oklch::OklchColor};
+
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error("Failed to read image {0}: {1}")]
+	Read(String, io::Error),
+
+	#[error("Failed to decode image {0}: {1}")]
+	Decode(String, image::ImageError),
+
+	#[error("Image {0} contains no pixels")]
+	Empty(String)
+}
+
+/// Images are scaled down to fit into a square of this size before clustering
+const SAMPLE_SIZE: u32 = 128;
+const CLUSTERS: usize = 8;
+const ITERATIONS: usize = 20;
+
+const MIN_PRIMARY_CONTRAST: f32 = 3.0;
+const MIN_ACCENT_CHROMA: f32 = 0.05;
+const MIN_SECONDARY_HUE_DISTANCE: f32 = PI / 6.0;
+
+struct Cluster {
+	center: Oklab,
+	weight: usize
+}
+
+fn distance_squared(a: &Oklab, b: &Oklab) -> f32 {
+	(a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)
+}
+
+fn nearest(centers: &[Oklab], pixel: &Oklab) -> usize {
+	centers
+		.iter()
+		.map(|center| distance_squared(center, pixel))
+		.enumerate()
+		.min_by(|(_, a), (_, b)| a.total_cmp(b))
+		.map(|(i, _)| i)
+		.unwrap()
+}
+
+fn mean(pixels: &[Oklab]) -> Oklab {
+	let mut sum = Oklab {
+		l: 0.0,
+		a: 0.0,
+		b: 0.0
+	};
+	for pixel in pixels {
+		sum.l += pixel.l;
+		sum.a += pixel.a;
+		sum.b += pixel.b;
+	}
+
+	let count = pixels.len() as f32;
+	Oklab {
+		l: sum.l / count,
+		a: sum.a / count,
+		b: sum.b / count
+	}
+}
+
+/// Groups the pixels into at most `k` clusters of similar colors. Returns the clusters sorted
+/// by the number of pixels they contain, largest first.
+fn k_means(pixels: &[Oklab], k: usize) -> Vec<Cluster> {
+	// The initial centers are chosen by repeatedly picking the pixel that is farthest away
+	// from all centers so far. Unlike random initialization, this makes sure that the same
+	// image always results in the same theme.
+	let average = mean(pixels);
+	let mut centers = vec![pixels[nearest(pixels, &average)]];
+	while centers.len() < k {
+		let (farthest, distance) = pixels
+			.iter()
+			.map(|pixel| distance_squared(&centers[nearest(&centers, pixel)], pixel))
+			.enumerate()
+			.max_by(|(_, a), (_, b)| a.total_cmp(b))
+			.unwrap();
+		if distance == 0.0 {
+			break;
+		}
+		centers.push(pixels[farthest]);
+	}
+
+	let mut assignments = vec![0; pixels.len()];
+	for _ in 0..ITERATIONS {
+		let mut changed = false;
+		for (pixel, assignment) in pixels.iter().zip(assignments.iter_mut()) {
+			let cluster = nearest(&centers, pixel);
+			changed |= cluster != *assignment;
+			*assignment = cluster;
+		}
+
+		for (i, center) in centers.iter_mut().enumerate() {
+			let members: Vec<Oklab> = pixels
+				.iter()
+				.zip(&assignments)
+				.filter(|(_, assignment)| **assignment == i)
+				.map(|(pixel, _)| *pixel)
+				.collect();
+			if !members.is_empty() {
+				*center = mean(&members);
+			}
+		}
+
+		if !changed {
+			break;
+		}
+	}
+
+	let mut clusters: Vec<Cluster> = centers
+		.into_iter()
+		.enumerate()
+		.map(|(i, center)| Cluster {
+			center,
+			weight: assignments.iter().filter(|a| **a == i).count()
+		})
+		.filter(|cluster| cluster.weight > 0)
+		.collect();
+	clusters.sort_by_key(|cluster| Reverse(cluster.weight));
+	clusters
+}
+
+/// Picks seed colors for a theme from the colors of the image at `path`. The color scheme is
+/// chosen from the average lightness of the image, unless `color_scheme` is set.
+pub fn extract_seeds(path: &Path, color_scheme: Option<ColorScheme>) -> Result<ThemeSeeds, Error> {
+	let path_name = path.display().to_string();
+
+	let image = ImageReader::open(path)
+		.and_then(|reader| reader.with_guessed_format())
+		.map_err(|e| Error::Read(path_name.clone(), e))?
+		.decode()
+		.map_err(|e| Error::Decode(path_name.clone(), e))?;
+	if image.width() == 0 || image.height() == 0 {
+		return Err(Error::Empty(path_name));
+	}
+
+	Ok(seeds_from_image(&image, color_scheme))
+}
+
+fn seeds_from_image(image: &DynamicImage, color_scheme: Option<ColorScheme>) -> ThemeSeeds {
+	let pixels: Vec<Oklab> = image
+		.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+		.into_rgb8()
+		.pixels()
+		.map(|pixel| {
+			srgb_to_oklab(Rgb {
+				r: pixel[0],
+				g: pixel[1],
+				b: pixel[2]
+			})
+		})
+		.collect();
+
+	let color_scheme = color_scheme.unwrap_or_else(|| generate::color_scheme_for(mean(&pixels).l));
+	let clusters = k_means(&pixels, CLUSTERS);
+	let colors: Vec<OklchColor> = clusters
+		.iter()
+		.map(|cluster| OklchColor::from_oklab(cluster.center))
+		.collect();
+
+	// The background takes on the hue of the most common color, but is toned down enough to
+	// put text on top of it
+	let dominant = colors[0];
+	let (background_lightness, direction) = match color_scheme {
+		ColorScheme::Dark => (dominant.lightness().clamp(0.16, 0.26), 1.0),
+		ColorScheme::Light => (dominant.lightness().clamp(0.92, 0.97), -1.0)
+	};
+	let background = Color::from_oklch(
+		dominant
+			.shade(background_lightness)
+			.with_chroma(dominant.chroma().min(0.04)),
+		0xff
+	);
+
+	// Accents are ranked by how colorful they are and how much of the image they cover
+	let mut accents: Vec<(OklchColor, f32)> = colors
+		.iter()
+		.zip(&clusters)
+		.map(|(color, cluster)| (*color, color.chroma() * (cluster.weight as f32).sqrt()))
+		.collect();
+	accents.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+	let accent = |color: OklchColor| {
+		let color = generate::ensure_contrast(color, background, MIN_PRIMARY_CONTRAST, direction);
+		Color::from_oklch(color, 0xff)
+	};
+
+	let primary = accents[0].0;
+	let secondary = accents[1..]
+		.iter()
+		.map(|(color, _)| *color)
+		.find(|color| {
+			color.chroma() >= MIN_ACCENT_CHROMA
+				&& generate::hue_difference(color.hue(), primary.hue()).abs()
+					>= MIN_SECONDARY_HUE_DISTANCE
+		})
+		.map(accent);
+
+	ThemeSeeds {
+		background,
+		primary: accent(primary),
+		secondary,
+		color_scheme: Some(color_scheme),
+		palette: colors
+			.iter()
+			.map(|color| Color::from_oklch(*color, 0xff))
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use image::{Rgb, RgbImage};
+
+	use super::*;
+
+	/// An image that is three quarters `background` and one quarter `accent`
+	fn image(background: &str, accent: &str) -> DynamicImage {
+		let background: Color = background.parse().unwrap();
+		let accent: Color = accent.parse().unwrap();
+		let image = RgbImage::from_fn(SAMPLE_SIZE, SAMPLE_SIZE, |x, _| {
+			let color = if x < SAMPLE_SIZE * 3 / 4 {
+				background
+			} else {
+				accent
+			};
+			Rgb([color.r, color.g, color.b])
+		});
+		DynamicImage::ImageRgb8(image)
+	}
+
+	fn color(hex: &str) -> Color {
+		hex.parse().unwrap()
+	}
+
+	fn scheme_of(color: Color) -> ColorScheme {
+		generate::color_scheme_for(color.into_oklch().lightness())
+	}
+
+	#[test]
+	fn should_extract_dominant_colors() {
+		let seeds = seeds_from_image(&image("#1a2b4c", "#e07020"), None);
+
+		assert_eq!(seeds.palette, vec![color("#1a2b4c"), color("#e07020")]);
+		assert_eq!(seeds.color_scheme, Some(ColorScheme::Dark));
+		assert_eq!(scheme_of(seeds.background), ColorScheme::Dark);
+		// The orange is more colorful than the larger blue, so it becomes the primary color
+		assert_eq!(seeds.primary, color("#e07020"));
+		let secondary = seeds.secondary.unwrap();
+		assert!(Color::contrast_ratio(secondary, seeds.background) >= MIN_PRIMARY_CONTRAST);
+	}
+
+	#[test]
+	fn should_detect_light_images() {
+		let seeds = seeds_from_image(&image("#f0ead8", "#2a7ab0"), None);
+
+		assert_eq!(seeds.palette, vec![color("#f0ead8"), color("#2a7ab0")]);
+		assert_eq!(seeds.color_scheme, Some(ColorScheme::Light));
+		assert_eq!(scheme_of(seeds.background), ColorScheme::Light);
+		assert_eq!(seeds.primary, color("#2a7ab0"));
+		// The background is too gray to be an accent
+		assert_eq!(seeds.secondary, None);
+	}
+
+	#[test]
+	fn should_keep_configured_color_scheme() {
+		let seeds = seeds_from_image(&image("#1a2b4c", "#e07020"), Some(ColorScheme::Light));
+
+		assert_eq!(seeds.color_scheme, Some(ColorScheme::Light));
+		assert_eq!(scheme_of(seeds.background), ColorScheme::Light);
+		assert!(Color::contrast_ratio(seeds.primary, seeds.background) >= MIN_PRIMARY_CONTRAST);
+	}
+}
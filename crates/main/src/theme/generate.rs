@@ -0,0 +1,206 @@
+use std::{
+	collections::BTreeMap,
+	f32::consts::{PI, TAU}
+};
+
+use super::derive;
+use crate::{
+	config::{ColorScheme, Terminal, Theme, ThemeMeta, UiTheme},
+	types::color::Color,
+	utils::oklch::OklchColor
+};
+
+/// The colors a theme is generated from
+pub struct ThemeSeeds {
+	pub background: Color,
+	pub primary: Color,
+	pub secondary: Option<Color>,
+	pub color_scheme: Option<ColorScheme>,
+	/// Colors to take the hues of the status and terminal colors from, if they are close
+	/// enough to the standard hue of that color
+	pub palette: Vec<Color>
+}
+
+pub const SECONDARY_HUE_OFFSET: f32 = PI / 4.0;
+const HARMONIZATION: f32 = 0.1;
+const MIN_PALETTE_CHROMA: f32 = 0.05;
+const MAX_PALETTE_HUE_DISTANCE: f32 = PI / 6.0;
+const MIN_ACCENT_CONTRAST: f32 = 4.5;
+
+// Hues of the accent colors in degrees
+const RED: f32 = 25.0;
+const YELLOW: f32 = 85.0;
+const GREEN: f32 = 145.0;
+const CYAN: f32 = 195.0;
+const BLUE: f32 = 255.0;
+const MAGENTA: f32 = 330.0;
+
+/// Guesses the color scheme of a theme from the (OKLab) lightness of its background
+pub fn color_scheme_for(lightness: f32) -> ColorScheme {
+	if lightness > 0.6 {
+		ColorScheme::Light
+	} else {
+		ColorScheme::Dark
+	}
+}
+
+/// The signed angle from `hue` to `target` along the shorter arc
+pub fn hue_difference(hue: f32, target: f32) -> f32 {
+	(target - hue + PI).rem_euclid(TAU) - PI
+}
+
+/// Shifts `hue` a bit towards `target` along the shorter arc, which makes colors look like
+/// they belong together without changing what color they are perceived as.
+fn harmonize(hue: f32, target: f32) -> f32 {
+	hue + hue_difference(hue, target) * HARMONIZATION
+}
+
+/// Moves the lightness of `color` in `direction` until it reaches a contrast ratio of at least
+/// `min_ratio` against `background`, or can't get any lighter or darker.
+pub fn ensure_contrast(
+	color: OklchColor,
+	background: Color,
+	min_ratio: f32,
+	direction: f32
+) -> OklchColor {
+	let mut color = color;
+	while Color::contrast_ratio(opaque(color), background) < min_ratio
+		&& (0.0..=1.0).contains(&(color.lightness() + 0.02 * direction))
+	{
+		color = color.lighten(0.02 * direction);
+	}
+	color
+}
+
+fn opaque(color: OklchColor) -> Color {
+	Color::from_oklch(color, 0xff)
+}
+
+pub fn generate(seeds: &ThemeSeeds) -> Theme {
+	let background = seeds.background.into_oklch();
+	let primary = seeds.primary.into_oklch();
+	let color_scheme = seeds
+		.color_scheme
+		.clone()
+		.unwrap_or_else(|| color_scheme_for(background.lightness()));
+
+	// Dark themes get lighter surfaces, light themes get darker ones
+	let (direction, accent_lightness) = match color_scheme {
+		ColorScheme::Dark => (1.0, 0.72),
+		ColorScheme::Light => (-1.0, 0.55)
+	};
+	let accent_chroma = primary.chroma().clamp(0.08, 0.18);
+	let palette: Vec<OklchColor> = seeds
+		.palette
+		.iter()
+		.map(|color| color.into_oklch())
+		.filter(|color| color.chroma() >= MIN_PALETTE_CHROMA)
+		.collect();
+	let accent = |hue: f32| {
+		let hue = harmonize(hue.to_radians(), primary.hue());
+		let distance = |color: &OklchColor| hue_difference(color.hue(), hue).abs();
+		let (hue, chroma) = palette
+			.iter()
+			.filter(|color| distance(color) < MAX_PALETTE_HUE_DISTANCE)
+			.min_by(|a, b| distance(a).total_cmp(&distance(b)))
+			.map_or((hue, accent_chroma), |color| {
+				(color.hue(), color.chroma().clamp(0.08, 0.18))
+			});
+
+		ensure_contrast(
+			OklchColor::new(accent_lightness, chroma, hue),
+			seeds.background,
+			MIN_ACCENT_CONTRAST,
+			direction
+		)
+	};
+
+	let surface = opaque(background.lighten(0.06 * direction));
+	let border = derive::border(seeds.background, &color_scheme);
+	let shadow = derive::shadow(seeds.background, &color_scheme);
+	let secondary = seeds
+		.secondary
+		.unwrap_or_else(|| opaque(primary.with_hue(primary.hue() + SECONDARY_HUE_OFFSET)));
+
+	let text = seeds.background.readable_text();
+	let success = opaque(accent(GREEN));
+	let info = opaque(accent(BLUE));
+	let warning = opaque(accent(YELLOW));
+	let error = opaque(accent(RED));
+
+	let ui = UiTheme {
+		color_scheme: color_scheme.clone(),
+		background: seeds.background,
+		surface,
+		primary: seeds.primary,
+		secondary,
+		border,
+		shadow,
+		text_background: text,
+		text_surface: surface.readable_text(),
+		text_primary: seeds.primary.readable_text(),
+		success,
+		info,
+		warning,
+		error,
+		text_success: success.readable_text(),
+		text_info: info.readable_text(),
+		text_warning: warning.readable_text(),
+		text_error: error.readable_text()
+	};
+
+	let text = text.into_oklch();
+	let (black, bright_black, white) = match color_scheme {
+		ColorScheme::Dark => (
+			background.darken(0.04),
+			background.lighten(0.25),
+			text.darken(0.12)
+		),
+		ColorScheme::Light => (
+			background.darken(0.15),
+			background.darken(0.35),
+			text.lighten(0.12)
+		)
+	};
+	let bright = |color: OklchColor| color.lighten(0.08).with_chroma(color.chroma() * 1.1);
+
+	let terminal = Terminal {
+		black: opaque(black),
+		red: opaque(accent(RED)),
+		green: opaque(accent(GREEN)),
+		yellow: opaque(accent(YELLOW)),
+		blue: opaque(accent(BLUE)),
+		magenta: opaque(accent(MAGENTA)),
+		cyan: opaque(accent(CYAN)),
+		white: opaque(white),
+		bright_black: opaque(bright_black),
+		bright_red: opaque(bright(accent(RED))),
+		bright_green: opaque(bright(accent(GREEN))),
+		bright_yellow: opaque(bright(accent(YELLOW))),
+		bright_blue: opaque(bright(accent(BLUE))),
+		bright_magenta: opaque(bright(accent(MAGENTA))),
+		bright_cyan: opaque(bright(accent(CYAN))),
+		bright_white: opaque(text),
+		foreground: ui.text_background,
+		background: ui.background,
+		cursor: ui.text_background,
+		cursor_text: ui.background,
+		selection_background: ui.surface,
+		selection_foreground: ui.text_surface,
+		palette_256: Vec::new()
+	};
+
+	let syntax = derive::syntax(&terminal);
+
+	Theme {
+		name: None,
+		extends: None,
+		meta: ThemeMeta::default(),
+		ui,
+		terminal,
+		palette: BTreeMap::new(),
+		syntax,
+		inherited: BTreeMap::new(),
+		derived: Vec::new()
+	}
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::{parse_color, terminal::TerminalColors, Error, ImportedTheme};
+use crate::types::color::Color;
+
+pub(super) const COLOR_NAMES: [&str; 8] = [
+	"black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"
+];
+
+/// Imports an Alacritty color scheme. Both the current TOML format and the older YAML
+/// format (used for files ending in `.yml` or `.yaml`) are supported.
+pub fn import(path: &Path, source: &str) -> Result<ImportedTheme, Error> {
+	let path_name = path.display().to_string();
+
+	let is_yaml = path
+		.extension()
+		.is_some_and(|extension| extension == "yml" || extension == "yaml");
+	let config: Value = if is_yaml {
+		serde_yaml::from_str(source).map_err(|e| Error::Parse(path_name, e.to_string()))?
+	} else {
+		toml::from_str(source).map_err(|e| Error::Parse(path_name, e.to_string()))?
+	};
+
+	// Besides colors, Alacritty accepts values like `CellForeground` in some places, which
+	// can't be represented in a theme
+	let color = |section: &str, key: &str| -> Result<Option<Color>, Error> {
+		match config
+			.pointer(&format!("/colors/{section}/{key}"))
+			.and_then(Value::as_str)
+		{
+			Some(value) if value.starts_with('#') || value.starts_with("0x") => {
+				parse_color(path, &format!("colors.{section}.{key}"), value).map(Some)
+			}
+			_ => Ok(None)
+		}
+	};
+
+	let mut colors = TerminalColors {
+		foreground: color("primary", "foreground")?,
+		background: color("primary", "background")?,
+		cursor: color("cursor", "cursor")?,
+		cursor_text: color("cursor", "text")?,
+		selection_background: color("selection", "background")?,
+		selection_foreground: color("selection", "text")?,
+		..Default::default()
+	};
+	for (i, name) in COLOR_NAMES.iter().enumerate() {
+		colors.palette[i] = color("normal", name)?;
+		colors.palette[i + 8] = color("bright", name)?;
+	}
+
+	Ok(ImportedTheme {
+		name: None,
+		theme: colors.into_theme(path)?
+	})
+}
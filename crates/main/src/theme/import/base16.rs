@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, path::Path};
+
+use serde_yaml::{Mapping, Value};
+
+use super::{parse_color, Error, ImportedTheme};
+use crate::{
+	config::{ColorScheme, Syntax, Terminal, Theme, ThemeMeta, UiTheme},
+	theme::{derive, generate},
+	types::color::Color
+};
+
+/// Imports a base16 or base24 scheme. Both the original format with the colors at the top
+/// level and the newer format with a `palette` section are supported.
+pub fn import(path: &Path, source: &str) -> Result<ImportedTheme, Error> {
+	let path_name = path.display().to_string();
+
+	let scheme: Mapping =
+		serde_yaml::from_str(source).map_err(|e| Error::Parse(path_name.clone(), e.to_string()))?;
+	let palette = scheme
+		.get("palette")
+		.and_then(Value::as_mapping)
+		.unwrap_or(&scheme);
+
+	let color = |key: &str| -> Result<Color, Error> {
+		let value = palette
+			.get(key)
+			.and_then(Value::as_str)
+			.ok_or_else(|| Error::MissingColor(path_name.clone(), key.to_string()))?;
+		parse_color(path, key, value)
+	};
+
+	// base24 schemes define separate bright colors, base16 schemes reuse the normal ones
+	let is_base24 = palette.contains_key("base12");
+	let bright = |base24_key: &str, base16_key: &str| {
+		if is_base24 {
+			color(base24_key)
+		} else {
+			color(base16_key)
+		}
+	};
+
+	let background = color("base00")?;
+	let color_scheme = match scheme.get("variant").and_then(Value::as_str) {
+		Some("light") => ColorScheme::Light,
+		Some("dark") => ColorScheme::Dark,
+		_ => generate::color_scheme_for(background.into_oklch().lightness())
+	};
+
+	let primary = color("base0D")?;
+	let success = color("base0B")?;
+	let info = color("base0C")?;
+	let warning = color("base0A")?;
+	let error = color("base08")?;
+
+	let ui = UiTheme {
+		color_scheme: color_scheme.clone(),
+		background,
+		surface: color("base01")?,
+		primary,
+		secondary: color("base0E")?,
+		border: color("base02")?,
+		shadow: derive::shadow(background, &color_scheme),
+		text_background: color("base05")?,
+		text_surface: color("base05")?,
+		text_primary: primary.readable_text(),
+		success,
+		info,
+		warning,
+		error,
+		text_success: success.readable_text(),
+		text_info: info.readable_text(),
+		text_warning: warning.readable_text(),
+		text_error: error.readable_text()
+	};
+
+	let terminal = Terminal {
+		black: background,
+		red: error,
+		green: success,
+		yellow: warning,
+		blue: primary,
+		magenta: color("base0E")?,
+		cyan: info,
+		white: color("base05")?,
+		bright_black: color("base03")?,
+		bright_red: bright("base12", "base08")?,
+		bright_green: bright("base14", "base0B")?,
+		bright_yellow: bright("base13", "base0A")?,
+		bright_blue: bright("base16", "base0D")?,
+		bright_magenta: bright("base17", "base0E")?,
+		bright_cyan: bright("base15", "base0C")?,
+		bright_white: color("base07")?,
+		foreground: ui.text_background,
+		background: ui.background,
+		cursor: ui.text_background,
+		cursor_text: ui.background,
+		selection_background: ui.border,
+		selection_foreground: ui.text_background,
+		palette_256: Vec::new()
+	};
+
+	// Follows the syntax highlighting guidelines of base16
+	let syntax = Syntax {
+		keyword: color("base0E")?,
+		string: color("base0B")?,
+		comment: color("base03")?,
+		function: color("base0D")?,
+		r#type: color("base0A")?,
+		constant: color("base09")?,
+		variable: color("base08")?,
+		diff_add: color("base0B")?,
+		diff_change: color("base0E")?,
+		diff_delete: color("base08")?,
+		extra: BTreeMap::new()
+	};
+
+	let name = ["name", "scheme"]
+		.into_iter()
+		.find_map(|key| scheme.get(key).and_then(Value::as_str))
+		.map(str::to_string);
+	let meta = ThemeMeta {
+		name: name.clone(),
+		author: scheme
+			.get("author")
+			.and_then(Value::as_str)
+			.filter(|author| !author.is_empty())
+			.map(str::to_string),
+		description: scheme
+			.get("description")
+			.and_then(Value::as_str)
+			.map(str::to_string),
+		..Default::default()
+	};
+
+	Ok(ImportedTheme {
+		name,
+		theme: Theme {
+			name: None,
+			extends: None,
+			meta,
+			ui,
+			terminal,
+			palette: BTreeMap::new(),
+			syntax,
+			inherited: BTreeMap::new(),
+			derived: Vec::new()
+		}
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The colors of a test scheme, as `baseXX: "hex"` lines indented by `indent`. The
+	/// colors are distinct, so that tests can tell which key a color was read from.
+	fn colors(indent: &str, base24: bool, background: &str) -> String {
+		let mut lines = vec![format!("{indent}base00: \"{background}\"")];
+		for i in 1..16 {
+			lines.push(format!("{indent}base0{i:X}: \"{i:x}0{i:x}0{i:x}0\""));
+		}
+		if base24 {
+			for i in 0x10..0x18 {
+				lines.push(format!(
+					"{indent}base{i:X}: \"{:x}{:x}0000\"",
+					i - 0x10,
+					i - 0x10
+				));
+			}
+		}
+		lines.join("\n")
+	}
+
+	fn hex(value: u32) -> Color {
+		Color::from((value << 8) | 0xff)
+	}
+
+	#[test]
+	fn should_import_top_level_base16_schemes() {
+		let source = format!(
+			"scheme: \"Test\"\nauthor: \"Someone\"\n{}",
+			colors("", false, "101010")
+		);
+		let imported = import(Path::new("test.yaml"), &source).unwrap();
+
+		assert_eq!(imported.name.as_deref(), Some("Test"));
+		assert_eq!(imported.theme.meta.author.as_deref(), Some("Someone"));
+		assert_eq!(imported.theme.terminal.red, hex(0x808080));
+		// base16 schemes have no separate bright colors
+		assert_eq!(imported.theme.terminal.bright_red, hex(0x808080));
+		assert_eq!(imported.theme.terminal.bright_blue, hex(0xd0d0d0));
+	}
+
+	#[test]
+	fn should_import_base24_schemes_with_palette() {
+		let source = format!(
+			"system: \"base24\"\nname: \"Test\"\npalette:\n{}",
+			colors("  ", true, "101010")
+		);
+		let imported = import(Path::new("test.yaml"), &source).unwrap();
+
+		assert_eq!(imported.name.as_deref(), Some("Test"));
+		assert_eq!(imported.theme.terminal.red, hex(0x808080));
+		assert_eq!(imported.theme.terminal.bright_red, hex(0x220000));
+		assert_eq!(imported.theme.terminal.bright_blue, hex(0x660000));
+	}
+
+	#[test]
+	fn should_pick_the_color_scheme() {
+		let scheme = |variant: &str, background: &str| {
+			let source = format!("{variant}palette:\n{}", colors("  ", false, background));
+			import(Path::new("test.yaml"), &source)
+				.unwrap()
+				.theme
+				.ui
+				.color_scheme
+		};
+
+		// The variant takes precedence over the background
+		assert_eq!(scheme("variant: \"light\"\n", "101010"), ColorScheme::Light);
+		assert_eq!(scheme("variant: \"dark\"\n", "f0f0f0"), ColorScheme::Dark);
+		assert_eq!(scheme("", "f0f0f0"), ColorScheme::Light);
+		assert_eq!(scheme("", "101010"), ColorScheme::Dark);
+	}
+}
@@ -0,0 +1,65 @@
+use std::path::Path;
+
+use super::{parse_color, terminal::TerminalColors, Error, ImportedTheme};
+
+/// Imports a foot color scheme. The colors are read from the `[colors]` section, or from
+/// `[colors-dark]`, which newer versions of foot use instead.
+pub fn import(path: &Path, source: &str) -> Result<ImportedTheme, Error> {
+	let mut colors = TerminalColors::default();
+	let mut in_colors_section = false;
+
+	for line in source.lines() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+			in_colors_section = section == "colors" || section == "colors-dark";
+			continue;
+		}
+		if !in_colors_section {
+			continue;
+		}
+
+		let Some((key, value)) = line.split_once('=') else {
+			continue;
+		};
+		let (key, value) = (key.trim(), value.trim());
+
+		// `cursor` sets the color of the text under the cursor and of the cursor itself
+		if key == "cursor" {
+			if let Some((text, cursor)) = value.split_once(char::is_whitespace) {
+				colors.cursor_text = Some(parse_color(path, key, text)?);
+				colors.cursor = Some(parse_color(path, key, cursor.trim())?);
+			}
+			continue;
+		}
+
+		let slot = match key {
+			"foreground" => &mut colors.foreground,
+			"background" => &mut colors.background,
+			"selection-background" => &mut colors.selection_background,
+			"selection-foreground" => &mut colors.selection_foreground,
+			_ => {
+				let index = if let Some(index) = key.strip_prefix("regular") {
+					index.parse::<usize>().ok()
+				} else if let Some(index) = key.strip_prefix("bright") {
+					index.parse::<usize>().ok().map(|i| i + 8)
+				} else {
+					None
+				};
+				match index {
+					Some(index @ 0..16) => &mut colors.palette[index],
+					_ => continue
+				}
+			}
+		};
+		*slot = Some(parse_color(path, key, value)?);
+	}
+
+	Ok(ImportedTheme {
+		name: None,
+		theme: colors.into_theme(path)?
+	})
+}
@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use plist::{Dictionary, Value};
+
+use super::{terminal::TerminalColors, Error, ImportedTheme};
+use crate::types::color::Color;
+
+/// Reads a color from an iTerm2 color dictionary, which stores the RGB components as real
+/// numbers from 0 to 1
+fn read_color(scheme: &Dictionary, key: &str) -> Option<Color> {
+	let color = scheme.get(key)?.as_dictionary()?;
+	let component = |name: &str| -> Option<u8> {
+		let value = color.get(name)?.as_real()?;
+		Some((value.clamp(0.0, 1.0) * 255.0).round() as u8)
+	};
+
+	Some(Color::new_rgba(
+		component("Red Component")?,
+		component("Green Component")?,
+		component("Blue Component")?,
+		0xff
+	))
+}
+
+/// Imports an iTerm2 `.itermcolors` file, which is a property list
+pub fn import(path: &Path, source: &[u8]) -> Result<ImportedTheme, Error> {
+	let scheme: Value = plist::from_bytes(source)
+		.map_err(|e| Error::Parse(path.display().to_string(), e.to_string()))?;
+	let scheme = scheme.as_dictionary().ok_or_else(|| {
+		Error::Parse(
+			path.display().to_string(),
+			"expected a dictionary of colors".to_string()
+		)
+	})?;
+
+	let mut colors = TerminalColors {
+		foreground: read_color(scheme, "Foreground Color"),
+		background: read_color(scheme, "Background Color"),
+		cursor: read_color(scheme, "Cursor Color"),
+		cursor_text: read_color(scheme, "Cursor Text Color"),
+		selection_background: read_color(scheme, "Selection Color"),
+		selection_foreground: read_color(scheme, "Selected Text Color"),
+		..Default::default()
+	};
+	for (i, color) in colors.palette.iter_mut().enumerate() {
+		*color = read_color(scheme, &format!("Ansi {i} Color"));
+	}
+
+	Ok(ImportedTheme {
+		name: None,
+		theme: colors.into_theme(path)?
+	})
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use super::{parse_color, terminal::TerminalColors, Error, ImportedTheme};
+
+/// Imports a kitty color scheme, which consists of `<option> <value>` lines like
+/// `color1 #cc241d`. Schemes from kitty-themes also contain metadata comments like
+/// `## name: Gruvbox Dark`, which is where the name is taken from.
+pub fn import(path: &Path, source: &str) -> Result<ImportedTheme, Error> {
+	let mut colors = TerminalColors::default();
+	let mut name = None;
+	let mut references = Vec::new();
+
+	for line in source.lines() {
+		let line = line.trim();
+		if let Some(scheme_name) = line.strip_prefix("## name:") {
+			name = Some(scheme_name.trim().to_string());
+			continue;
+		}
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let Some((key, value)) = line.split_once(char::is_whitespace) else {
+			continue;
+		};
+		let value = value.trim();
+
+		// Besides colors, kitty accepts `none`, and `background` or `foreground` for the
+		// colors of the cell, for some options. The cell colors are approximated with the
+		// colors of the scheme once all of them are known.
+		match value {
+			"none" => continue,
+			"background" | "foreground" => {
+				references.push((key, value));
+				continue;
+			}
+			_ => ()
+		}
+
+		let slot = match key {
+			"foreground" => &mut colors.foreground,
+			"background" => &mut colors.background,
+			"cursor" => &mut colors.cursor,
+			"cursor_text_color" => &mut colors.cursor_text,
+			"selection_background" => &mut colors.selection_background,
+			"selection_foreground" => &mut colors.selection_foreground,
+			_ => match key
+				.strip_prefix("color")
+				.and_then(|i| i.parse::<usize>().ok())
+			{
+				Some(index @ 0..16) => &mut colors.palette[index],
+				_ => continue
+			}
+		};
+		*slot = Some(parse_color(path, key, value)?);
+	}
+
+	for (key, value) in references {
+		let color = match value {
+			"background" => colors.background,
+			_ => colors.foreground
+		};
+		match key {
+			"cursor" => colors.cursor = color,
+			"cursor_text_color" => colors.cursor_text = color,
+			"selection_background" => colors.selection_background = color,
+			"selection_foreground" => colors.selection_foreground = color,
+			_ => ()
+		}
+	}
+
+	Ok(ImportedTheme {
+		name,
+		theme: colors.into_theme(path)?
+	})
+}
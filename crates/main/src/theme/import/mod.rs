@@ -0,0 +1,267 @@
+use std::{fs, io, path::Path};
+
+use thiserror::Error;
+
+use crate::{
+	config::Theme,
+	types::color::{Color, ColorParseError}
+};
+
+pub mod alacritty;
+pub mod base16;
+pub mod foot;
+pub mod iterm2;
+pub mod kitty;
+pub mod terminal;
+pub mod xresources;
+
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error("Failed to read {0}: {1}")]
+	Read(String, io::Error),
+
+	#[error("Invalid syntax in {0}: {1}")]
+	Parse(String, String),
+
+	#[error("{0} doesn't define the color \"{1}\"")]
+	MissingColor(String, String),
+
+	#[error("Invalid value for color \"{1}\" in {0}: {2}")]
+	InvalidColor(String, String, ColorParseError)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ImportFormat {
+	Base16,
+	Kitty,
+	Alacritty,
+	Foot,
+	Iterm2,
+	Xresources
+}
+
+pub struct ImportedTheme {
+	/// The name the imported file gives the theme, if any
+	pub name: Option<String>,
+	pub theme: Theme
+}
+
+pub fn import(format: ImportFormat, path: &Path) -> Result<ImportedTheme, Error> {
+	let read_error = |e| Error::Read(path.display().to_string(), e);
+
+	// Property lists may be binary, all other formats are text
+	if let ImportFormat::Iterm2 = format {
+		let source = fs::read(path).map_err(read_error)?;
+		return iterm2::import(path, &source);
+	}
+
+	let source = fs::read_to_string(path).map_err(read_error)?;
+	match format {
+		ImportFormat::Base16 => base16::import(path, &source),
+		ImportFormat::Kitty => kitty::import(path, &source),
+		ImportFormat::Alacritty => alacritty::import(path, &source),
+		ImportFormat::Foot => foot::import(path, &source),
+		ImportFormat::Xresources => xresources::import(path, &source),
+		ImportFormat::Iterm2 => unreachable!()
+	}
+}
+
+/// Turns a human-readable name like "Gruvbox Dark, Hard" into a theme name like
+/// "gruvbox-dark-hard"
+pub fn theme_name(name: &str) -> String {
+	name.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(str::to_lowercase)
+		.collect::<Vec<_>>()
+		.join("-")
+}
+
+/// Parses a hex color that may start with `#`, `0x` or nothing at all, as different formats
+/// disagree on that.
+fn parse_color(path: &Path, key: &str, value: &str) -> Result<Color, Error> {
+	let value = value.trim();
+	let digits = value
+		.strip_prefix('#')
+		.or_else(|| value.strip_prefix("0x"))
+		.unwrap_or(value);
+
+	format!("#{digits}")
+		.parse()
+		.map_err(|e| Error::InvalidColor(path.display().to_string(), key.to_string(), e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The normal colors of the test schemes, from black to white
+	const NORMAL: [&str; 8] = [
+		"000000", "cc0000", "00cc00", "cccc00", "0000cc", "cc00cc", "00cccc", "cccccc"
+	];
+
+	fn color(hex: u32) -> Color {
+		Color::from((hex << 8) | 0xff)
+	}
+
+	/// Checks the colors all test schemes define: the normal colors, a bright red, and the
+	/// foreground and background
+	fn assert_scheme_colors(theme: &Theme) {
+		assert_eq!(theme.terminal.black, color(0x000000));
+		assert_eq!(theme.terminal.red, color(0xcc0000));
+		assert_eq!(theme.terminal.white, color(0xcccccc));
+		assert_eq!(theme.terminal.bright_red, color(0xff0000));
+		// Missing bright colors fall back to the normal ones
+		assert_eq!(theme.terminal.bright_green, color(0x00cc00));
+		assert_eq!(theme.terminal.background, color(0x111111));
+		assert_eq!(theme.terminal.foreground, color(0xeeeeee));
+	}
+
+	#[test]
+	fn should_import_kitty_schemes() {
+		let mut source = [
+			"## name: Test Scheme",
+			"foreground #eeeeee",
+			"background #111111",
+			"cursor_text_color background",
+			"selection_foreground none",
+			"color9 #ff0000",
+			""
+		]
+		.join("\n");
+		for (i, hex) in NORMAL.iter().enumerate() {
+			source += &format!("color{i} #{hex}\n");
+		}
+
+		let imported = kitty::import(Path::new("test.conf"), &source).unwrap();
+		assert_eq!(imported.name.as_deref(), Some("Test Scheme"));
+		assert_scheme_colors(&imported.theme);
+		assert_eq!(imported.theme.terminal.cursor_text, color(0x111111));
+		assert_eq!(
+			imported.theme.terminal.selection_foreground,
+			color(0xeeeeee)
+		);
+	}
+
+	#[test]
+	fn should_import_alacritty_toml_schemes() {
+		let mut source = [
+			"[colors.primary]",
+			r##"background = "#111111""##,
+			r##"foreground = "#eeeeee""##,
+			"[colors.cursor]",
+			r#"cursor = "CellForeground""#,
+			r#"text = "CellBackground""#,
+			"[colors.bright]",
+			r##"red = "#ff0000""##,
+			"[colors.normal]",
+			""
+		]
+		.join("\n");
+		for (name, hex) in alacritty::COLOR_NAMES.iter().zip(NORMAL) {
+			source += &format!("{name} = \"#{hex}\"\n");
+		}
+
+		let imported = alacritty::import(Path::new("test.toml"), &source).unwrap();
+		assert_scheme_colors(&imported.theme);
+	}
+
+	#[test]
+	fn should_import_alacritty_yaml_schemes() {
+		let mut source = [
+			"colors:",
+			"  primary:",
+			"    background: '0x111111'",
+			"    foreground: '0xeeeeee'",
+			"  bright:",
+			"    red: '0xff0000'",
+			"  normal:",
+			""
+		]
+		.join("\n");
+		for (name, hex) in alacritty::COLOR_NAMES.iter().zip(NORMAL) {
+			source += &format!("    {name}: '0x{hex}'\n");
+		}
+
+		let imported = alacritty::import(Path::new("test.yml"), &source).unwrap();
+		assert_scheme_colors(&imported.theme);
+	}
+
+	#[test]
+	fn should_import_foot_schemes() {
+		let mut source = [
+			"[main]",
+			"font=monospace",
+			"[colors]",
+			"foreground=eeeeee",
+			"background=111111",
+			"cursor=222222 dddddd",
+			"bright1=ff0000",
+			""
+		]
+		.join("\n");
+		for (i, hex) in NORMAL.iter().enumerate() {
+			source += &format!("regular{i}={hex}\n");
+		}
+
+		let imported = foot::import(Path::new("test.ini"), &source).unwrap();
+		assert_scheme_colors(&imported.theme);
+		assert_eq!(imported.theme.terminal.cursor_text, color(0x222222));
+		assert_eq!(imported.theme.terminal.cursor, color(0xdddddd));
+	}
+
+	#[test]
+	fn should_import_xresources() {
+		let mut source = [
+			"! Test scheme",
+			"#define bg #111111",
+			"*.background: bg",
+			"URxvt.foreground: #eeeeee",
+			"*color9: #ff0000",
+			""
+		]
+		.join("\n");
+		for (i, hex) in NORMAL.iter().enumerate() {
+			source += &format!("*.color{i}: #{hex}\n");
+		}
+
+		let imported = xresources::import(Path::new(".Xresources"), &source).unwrap();
+		assert_scheme_colors(&imported.theme);
+	}
+
+	#[test]
+	fn should_import_iterm2_schemes() {
+		let entry = |key: &str, hex: u32| {
+			let component = |shift: u32| ((hex >> shift) & 0xff) as f32 / 255.0;
+			format!(
+				"<key>{key}</key><dict><key>Red Component</key><real>{}</real><key>Green \
+				 Component</key><real>{}</real><key>Blue Component</key><real>{}</real></dict>",
+				component(16),
+				component(8),
+				component(0)
+			)
+		};
+
+		let mut entries =
+			entry("Background Color", 0x111111) + &entry("Foreground Color", 0xeeeeee);
+		entries += &entry("Ansi 9 Color", 0xff0000);
+		for (i, hex) in NORMAL.iter().enumerate() {
+			entries += &entry(
+				&format!("Ansi {i} Color"),
+				u32::from_str_radix(hex, 16).unwrap()
+			);
+		}
+		let source = format!(
+			"<?xml version=\"1.0\" encoding=\"UTF-8\"?><plist \
+			 version=\"1.0\"><dict>{entries}</dict></plist>"
+		);
+
+		let imported = iterm2::import(Path::new("test.itermcolors"), source.as_bytes()).unwrap();
+		assert_scheme_colors(&imported.theme);
+	}
+
+	#[test]
+	fn should_turn_scheme_names_into_theme_names() {
+		assert_eq!(theme_name("Gruvbox dark, medium"), "gruvbox-dark-medium");
+		assert_eq!(theme_name("  Tokyo Night (Storm) "), "tokyo-night-storm");
+	}
+}
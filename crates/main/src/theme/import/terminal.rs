@@ -0,0 +1,131 @@
+use std::{collections::BTreeMap, path::Path};
+
+use super::Error;
+use crate::{
+	config::{Terminal, Theme, ThemeMeta, UiTheme},
+	theme::{derive, generate},
+	types::color::Color
+};
+
+/// The colors a terminal color scheme defines, in the order of their ANSI color indices
+const COLOR_NAMES: [&str; 16] = [
+	"black",
+	"red",
+	"green",
+	"yellow",
+	"blue",
+	"magenta",
+	"cyan",
+	"white",
+	"bright_black",
+	"bright_red",
+	"bright_green",
+	"bright_yellow",
+	"bright_blue",
+	"bright_magenta",
+	"bright_cyan",
+	"bright_white"
+];
+
+/// The colors read from a terminal color scheme, before they are turned into a theme
+#[derive(Debug, Default)]
+pub struct TerminalColors {
+	pub palette: [Option<Color>; 16],
+	pub foreground: Option<Color>,
+	pub background: Option<Color>,
+	pub cursor: Option<Color>,
+	pub cursor_text: Option<Color>,
+	pub selection_background: Option<Color>,
+	pub selection_foreground: Option<Color>
+}
+
+impl TerminalColors {
+	/// Builds a theme from the terminal colors. The eight normal colors are required; missing
+	/// bright colors fall back to the normal ones, and missing foreground and background
+	/// colors to white and black. The cursor and selection colors fall back to the foreground,
+	/// background and surface colors.
+	pub fn into_theme(self, path: &Path) -> Result<Theme, Error> {
+		let mut palette = [Color::default(); 16];
+		for (i, name) in COLOR_NAMES.iter().enumerate() {
+			palette[i] = match (self.palette[i], i) {
+				(Some(color), _) => color,
+				(None, 8..) => palette[i - 8],
+				(None, _) => {
+					return Err(Error::MissingColor(
+						path.display().to_string(),
+						name.to_string()
+					))
+				}
+			};
+		}
+
+		let background = self.background.unwrap_or(palette[0]);
+		let foreground = self.foreground.unwrap_or(palette[7]);
+		let surface = Color::blend(background, foreground, 0.08);
+
+		let terminal = Terminal {
+			black: palette[0],
+			red: palette[1],
+			green: palette[2],
+			yellow: palette[3],
+			blue: palette[4],
+			magenta: palette[5],
+			cyan: palette[6],
+			white: palette[7],
+			bright_black: palette[8],
+			bright_red: palette[9],
+			bright_green: palette[10],
+			bright_yellow: palette[11],
+			bright_blue: palette[12],
+			bright_magenta: palette[13],
+			bright_cyan: palette[14],
+			bright_white: palette[15],
+			foreground,
+			background,
+			cursor: self.cursor.unwrap_or(foreground),
+			cursor_text: self.cursor_text.unwrap_or(background),
+			selection_background: self.selection_background.unwrap_or(surface),
+			selection_foreground: self.selection_foreground.unwrap_or(foreground),
+			palette_256: Vec::new()
+		};
+
+		let color_scheme = generate::color_scheme_for(background.into_oklch().lightness());
+
+		let ui = UiTheme {
+			color_scheme: color_scheme.clone(),
+			background,
+			surface,
+			primary: terminal.blue,
+			secondary: terminal.magenta,
+			border: self
+				.selection_background
+				.unwrap_or_else(|| derive::border(background, &color_scheme)),
+			shadow: derive::shadow(background, &color_scheme),
+			text_background: foreground,
+			text_surface: foreground,
+			text_primary: terminal.blue.readable_text(),
+			success: terminal.green,
+			info: terminal.cyan,
+			warning: terminal.yellow,
+			error: terminal.red,
+			text_success: terminal.green.readable_text(),
+			text_info: terminal.cyan.readable_text(),
+			text_warning: terminal.yellow.readable_text(),
+			text_error: terminal.red.readable_text()
+		};
+
+		let syntax = derive::syntax(&terminal);
+
+		Ok(Theme {
+			name: None,
+			extends: None,
+			meta: ThemeMeta::default(),
+			ui,
+			terminal,
+			palette: BTreeMap::new(),
+			syntax,
+			inherited: BTreeMap::new(),
+			derived: Vec::new()
+		})
+	}
+}
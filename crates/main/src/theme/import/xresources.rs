@@ -0,0 +1,58 @@
+use std::{collections::HashMap, path::Path};
+
+use super::{parse_color, terminal::TerminalColors, Error, ImportedTheme};
+
+/// Imports the colors from an Xresources file, like `*.color0: #282828`. Resources for
+/// specific programs, like `URxvt.color0`, are read as well, and simple `#define`s are
+/// substituted.
+pub fn import(path: &Path, source: &str) -> Result<ImportedTheme, Error> {
+	let mut colors = TerminalColors::default();
+	let mut defines = HashMap::new();
+
+	for line in source.lines() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('!') {
+			continue;
+		}
+
+		if let Some(define) = line.strip_prefix("#define") {
+			let mut parts = define.split_whitespace();
+			if let (Some(name), Some(value)) = (parts.next(), parts.next()) {
+				defines.insert(name.to_string(), value.to_string());
+			}
+			continue;
+		}
+
+		let Some((resource, value)) = line.split_once(':') else {
+			continue;
+		};
+		let key = resource
+			.trim()
+			.rsplit(['.', '*'])
+			.next()
+			.unwrap_or_default();
+		let value = value.trim();
+		let value = defines.get(value).map_or(value, String::as_str);
+
+		let slot = match key {
+			"foreground" => &mut colors.foreground,
+			"background" => &mut colors.background,
+			"cursorColor" => &mut colors.cursor,
+			"highlightColor" => &mut colors.selection_background,
+			"highlightTextColor" => &mut colors.selection_foreground,
+			_ => match key
+				.strip_prefix("color")
+				.and_then(|i| i.parse::<usize>().ok())
+			{
+				Some(index @ 0..16) => &mut colors.palette[index],
+				_ => continue
+			}
+		};
+		*slot = Some(parse_color(path, key, value)?);
+	}
+
+	Ok(ImportedTheme {
+		name: None,
+		theme: colors.into_theme(path)?
+	})
+}
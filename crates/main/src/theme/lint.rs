@@ -0,0 +1,355 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+	config::{ColorScheme, Theme},
+	types::color::Color,
+	utils::oklch::OklchColor
+};
+
+/// The minimum WCAG 2.x contrast ratio for text (level AA)
+const MIN_TEXT_CONTRAST: f32 = 4.5;
+/// The WCAG 2.x contrast ratio below which even large or bold text is hard to read
+const MIN_LABEL_CONTRAST: f32 = 3.0;
+/// The minimum absolute APCA Lc value for text
+const MIN_TEXT_LC: f32 = 60.0;
+/// The minimum WCAG 2.x contrast ratio of terminal colors against the background
+const MIN_TERMINAL_CONTRAST: f32 = 3.0;
+/// Colors closer than this in OKLab are hard to tell apart
+const MIN_DISTANCE: f32 = 0.02;
+/// How far a lightness adjustment moves a color in one step
+const LIGHTNESS_STEP: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+	Warning,
+	Error
+}
+
+impl fmt::Display for Severity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Warning => write!(f, "warning"),
+			Self::Error => write!(f, "error")
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct Issue {
+	pub severity: Severity,
+	pub message: String,
+	pub suggestion: Option<String>
+}
+
+impl fmt::Display for Issue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.severity, self.message)?;
+		if let Some(suggestion) = &self.suggestion {
+			write!(f, "\n  suggestion: {suggestion}")?;
+		}
+		Ok(())
+	}
+}
+
+/// Checks a theme for colors that are hard to read or to tell apart
+pub fn lint(theme: &Theme) -> Vec<Issue> {
+	let mut issues = Vec::new();
+	check_text(theme, &mut issues);
+	check_terminal_contrast(theme, &mut issues);
+	check_bright_colors(theme, &mut issues);
+	check_duplicates(theme, &mut issues);
+	issues
+}
+
+fn text_passes(text: Color, background: Color) -> bool {
+	Color::contrast_ratio(text, background) >= MIN_TEXT_CONTRAST
+		&& Color::apca_contrast(text, background).abs() >= MIN_TEXT_LC
+}
+
+/// Finds the smallest change to the OKLCH lightness of `color` for which `passes` holds.
+/// Moving the color away from the lightness of `background` is tried first.
+fn adjust_lightness(
+	color: Color,
+	background: Color,
+	passes: impl Fn(Color) -> bool
+) -> Option<Color> {
+	let original = color.into_oklch();
+	let away = if original.lightness() >= background.into_oklch().lightness() {
+		1.0
+	} else {
+		-1.0
+	};
+
+	for direction in [away, -away] {
+		let mut lightness = original.lightness();
+		while (0.0..=1.0).contains(&lightness) {
+			lightness += LIGHTNESS_STEP * direction;
+			let candidate = Color::from_oklch(original.shade(lightness.clamp(0.0, 1.0)), color.a);
+			if passes(candidate) {
+				return Some(candidate);
+			}
+		}
+	}
+
+	None
+}
+
+fn lightness_suggestion(name: &str, original: Color, adjusted: Color) -> String {
+	format!(
+		"set {name} to {} (OKLCH lightness {:.2} instead of {:.2})",
+		adjusted,
+		adjusted.into_oklch().lightness(),
+		original.into_oklch().lightness()
+	)
+}
+
+fn check_text(theme: &Theme, issues: &mut Vec<Issue>) {
+	let colors: HashMap<&str, Color> = theme.ui.colors().into_iter().collect();
+
+	// Body text needs the full contrast, labels on accent colors only count as errors below
+	// `MIN_LABEL_CONTRAST`
+	let backgrounds = [
+		("background", MIN_TEXT_CONTRAST),
+		("surface", MIN_TEXT_CONTRAST),
+		("primary", MIN_LABEL_CONTRAST),
+		("success", MIN_LABEL_CONTRAST),
+		("info", MIN_LABEL_CONTRAST),
+		("warning", MIN_LABEL_CONTRAST),
+		("error", MIN_LABEL_CONTRAST)
+	];
+
+	for (background_name, min_contrast) in backgrounds {
+		let text_name = format!("text_{background_name}");
+		let text = colors[text_name.as_str()];
+		let background = colors[background_name];
+
+		if text_passes(text, background) {
+			continue;
+		}
+
+		let ratio = Color::contrast_ratio(text, background);
+		let lc = Color::apca_contrast(text, background).abs();
+		let severity = if ratio < min_contrast {
+			Severity::Error
+		} else {
+			Severity::Warning
+		};
+
+		// Some colors have no text color that satisfies both WCAG and APCA, so the suggestion
+		// falls back to satisfying WCAG alone
+		let adjusted =
+			adjust_lightness(text, background, |c| text_passes(c, background)).or_else(|| {
+				adjust_lightness(text, background, |c| {
+					Color::contrast_ratio(c, background) >= MIN_TEXT_CONTRAST
+				})
+			});
+
+		issues.push(Issue {
+			severity,
+			message: format!(
+				"{text_name} on {background_name} has a contrast ratio of {ratio:.2}:1 and an \
+				 APCA contrast of Lc {lc:.0} (needs {MIN_TEXT_CONTRAST}:1 and Lc {MIN_TEXT_LC})"
+			),
+			suggestion: adjusted.map(|adjusted| lightness_suggestion(&text_name, text, adjusted))
+		});
+	}
+}
+
+fn check_terminal_contrast(theme: &Theme, issues: &mut Vec<Issue>) {
+	let background = theme.ui.background;
+
+	// The color closest to the background is usually meant to blend in with it
+	let background_like = match theme.ui.color_scheme {
+		ColorScheme::Dark => "black",
+		ColorScheme::Light => "white"
+	};
+
+	for (name, color) in theme.terminal.colors() {
+		if name == background_like {
+			continue;
+		}
+
+		let passes = |c: Color| Color::contrast_ratio(c, background) >= MIN_TERMINAL_CONTRAST;
+		if passes(color) {
+			continue;
+		}
+
+		issues.push(Issue {
+			severity: Severity::Warning,
+			message: format!(
+				"terminal.{name} has a contrast ratio of {:.2}:1 against the background (needs \
+				 {MIN_TERMINAL_CONTRAST}:1)",
+				Color::contrast_ratio(color, background)
+			),
+			suggestion: adjust_lightness(color, background, passes)
+				.map(|adjusted| lightness_suggestion(&format!("terminal.{name}"), color, adjusted))
+		});
+	}
+}
+
+fn distance(col1: Color, col2: Color) -> f32 {
+	OklchColor::distance(col1.into_oklch(), col2.into_oklch())
+}
+
+/// Suggests a lightness for `color` that makes it distinct from `other`
+fn distinct_suggestion(name: &str, color: Color, other: Color, theme: &Theme) -> Option<String> {
+	let direction = match theme.ui.color_scheme {
+		ColorScheme::Dark => 1.0,
+		ColorScheme::Light => -1.0
+	};
+
+	let original = color.into_oklch();
+	let mut lightness = original.lightness();
+	while (0.0..=1.0).contains(&lightness) {
+		lightness += LIGHTNESS_STEP * direction;
+		let candidate = Color::from_oklch(original.shade(lightness.clamp(0.0, 1.0)), color.a);
+		if distance(candidate, other) >= MIN_DISTANCE * 3.0 {
+			return Some(lightness_suggestion(name, color, candidate));
+		}
+	}
+	None
+}
+
+fn check_bright_colors(theme: &Theme, issues: &mut Vec<Issue>) {
+	let colors = theme.terminal.colors();
+	for ((name, normal), (bright_name, bright)) in colors[..8].iter().zip(&colors[8..]) {
+		if distance(*normal, *bright) >= MIN_DISTANCE {
+			continue;
+		}
+
+		issues.push(Issue {
+			severity: Severity::Warning,
+			message: format!("terminal.{bright_name} is almost identical to terminal.{name}"),
+			suggestion: distinct_suggestion(
+				&format!("terminal.{bright_name}"),
+				*bright,
+				*normal,
+				theme
+			)
+		});
+	}
+}
+
+fn check_duplicates(theme: &Theme, issues: &mut Vec<Issue>) {
+	let ui = &theme.ui;
+	let terminal = &theme.terminal;
+
+	let groups = [
+		[
+			("ui.primary", ui.primary),
+			("ui.secondary", ui.secondary),
+			("ui.success", ui.success),
+			("ui.info", ui.info),
+			("ui.warning", ui.warning),
+			("ui.error", ui.error)
+		],
+		[
+			("terminal.red", terminal.red),
+			("terminal.green", terminal.green),
+			("terminal.yellow", terminal.yellow),
+			("terminal.blue", terminal.blue),
+			("terminal.magenta", terminal.magenta),
+			("terminal.cyan", terminal.cyan)
+		]
+	];
+
+	for group in groups {
+		for (i, (name, color)) in group.iter().enumerate() {
+			for (other_name, other) in &group[i + 1..] {
+				if distance(*color, *other) >= MIN_DISTANCE {
+					continue;
+				}
+
+				issues.push(Issue {
+					severity: Severity::Warning,
+					message: format!("{other_name} is almost identical to {name}"),
+					suggestion: distinct_suggestion(other_name, *other, *color, theme)
+				});
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, path::Path};
+
+	use super::*;
+	use crate::theme::derive;
+
+	/// Errors in the built-in themes that come from their upstream palettes, as the theme file
+	/// and the text colors with too little contrast
+	const KNOWN_ISSUES: [(&str, &[&str]); 5] = [
+		("dracula/theme.toml", &["text_error"]),
+		(
+			"gruvbox/theme.toml",
+			&["text_primary", "text_success", "text_warning"]
+		),
+		(
+			"gruvbox/light.toml",
+			&["text_primary", "text_info", "text_error"]
+		),
+		(
+			"catppuccin/latte.toml",
+			&["text_primary", "text_success", "text_warning"]
+		),
+		("tokyonight/theme.toml", &["text_success"])
+	];
+
+	fn read_theme(path: &Path) -> Theme {
+		let mut table: toml::Table = toml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+		derive::fill_derived(&mut table);
+		table.try_into().unwrap()
+	}
+
+	fn errors(theme: &Theme) -> Vec<String> {
+		lint(theme)
+			.into_iter()
+			.filter(|issue| issue.severity == Severity::Error)
+			.map(|issue| issue.to_string())
+			.collect()
+	}
+
+	#[test]
+	fn built_in_themes_should_have_no_new_errors() {
+		let themes_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../assets/themes");
+
+		// Every theme directory contains a `theme.toml`, variants of the theme, or both
+		let paths = fs::read_dir(&themes_dir)
+			.unwrap()
+			.flat_map(|entry| fs::read_dir(entry.unwrap().path()).unwrap())
+			.map(|entry| entry.unwrap().path())
+			.filter(|path| path.extension().is_some_and(|ext| ext == "toml"));
+
+		for path in paths {
+			let file = path.strip_prefix(&themes_dir).unwrap();
+			let known = KNOWN_ISSUES
+				.iter()
+				.find(|(known_file, _)| Path::new(known_file) == file)
+				.map_or(&[][..], |(_, colors)| *colors);
+
+			let errors: Vec<String> = errors(&read_theme(&path))
+				.into_iter()
+				.filter(|error| {
+					!known
+						.iter()
+						.any(|color| error.starts_with(&format!("error: {color} on ")))
+				})
+				.collect();
+			assert!(errors.is_empty(), "{}: {errors:#?}", path.display());
+		}
+	}
+
+	#[test]
+	fn should_report_unreadable_text() {
+		let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+			.join("../../assets/themes/catppuccin/frappe.toml");
+		let mut theme = read_theme(&path);
+		assert!(errors(&theme).is_empty());
+
+		theme.ui.text_primary = theme.ui.primary.lighten(0.05);
+		let errors = errors(&theme);
+		assert_eq!(errors.len(), 1);
+		assert!(errors[0].starts_with("error: text_primary on primary"));
+	}
+}
@@ -0,0 +1,8 @@
+pub mod accent;
+pub mod derive;
+pub mod export;
+pub mod extract;
+pub mod generate;
+pub mod import;
+pub mod lint;
+pub mod remap;
@@ -0,0 +1,287 @@
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+
+use crate::{
+	config::{ModuleConfig, ModuleConfigValue, Theme},
+	theme::derive,
+	types::color::Color
+};
+
+/// The module config key that holds the color mapping
+pub const COLORS_KEY: &str = "colors";
+
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error("The \"{COLORS_KEY}\" option must be a table of color expressions")]
+	InvalidMapping,
+
+	#[error("The color expression for \"{0}\" must be a string")]
+	InvalidValue(String),
+
+	#[error("\"{0}\" is not a color of the theme")]
+	UnknownRole(String),
+
+	#[error("Invalid color expression \"{0}\": {1}")]
+	InvalidExpression(String, String),
+
+	#[error("Failed to remap the theme colors: {0}")]
+	Convert(#[from] serde_json::Error)
+}
+
+/// Replaces colors of `theme` according to the `colors` table in `module_config`, which maps
+/// color roles like `ui.border` to color expressions. All expressions are evaluated against
+/// the original theme, so the order of the mapping doesn't matter.
+pub fn remap(theme: &Theme, module_config: &ModuleConfig) -> Result<Theme, Error> {
+	let mapping = match module_config.get(COLORS_KEY) {
+		None | Some(ModuleConfigValue::Nil) => return Ok(theme.clone()),
+		Some(ModuleConfigValue::Map(mapping)) if mapping.is_empty() => return Ok(theme.clone()),
+		Some(ModuleConfigValue::Map(mapping)) => mapping,
+		Some(_) => return Err(Error::InvalidMapping)
+	};
+
+	let original = serde_json::to_value(theme)?;
+	let mut remapped = original.clone();
+
+	for (role, expression) in mapping {
+		let ModuleConfigValue::String(expression) = expression else {
+			return Err(Error::InvalidValue(role.clone()));
+		};
+
+		let (section, name) = split_role(role);
+		if section != "palette" && lookup(&original, role).is_none() {
+			return Err(Error::UnknownRole(role.clone()));
+		}
+
+		let color = evaluate(expression, &original)
+			.map_err(|err| Error::InvalidExpression(expression.clone(), err))?;
+		remapped[section][name] = color.to_string().into();
+	}
+
+	let mut remapped: Theme = serde_json::from_value(remapped)?;
+	remapped.name = theme.name.clone();
+	remapped.inherited = theme.inherited.clone();
+	remapped.derived = theme.derived.clone();
+	remapped.terminal.palette_256 = derive::palette_256(&remapped.terminal);
+	Ok(remapped)
+}
+
+/// Splits a role like `terminal.blue` into its section and name. Roles without a section
+/// refer to `ui`.
+fn split_role(role: &str) -> (&str, &str) {
+	role.split_once('.').unwrap_or(("ui", role))
+}
+
+fn lookup(theme: &serde_json::Value, role: &str) -> Option<Color> {
+	let (section, name) = split_role(role);
+	theme.get(section)?.get(name)?.as_str()?.parse().ok()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+	Name(String),
+	Color(Color),
+	Number(f32),
+	OpenParen,
+	CloseParen,
+	Comma
+}
+
+impl fmt::Display for Token {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Name(name) => write!(f, "{name}"),
+			Self::Color(color) => write!(f, "{color}"),
+			Self::Number(number) => write!(f, "{number}"),
+			Self::OpenParen => write!(f, "("),
+			Self::CloseParen => write!(f, ")"),
+			Self::Comma => write!(f, ",")
+		}
+	}
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+	let mut tokens = Vec::new();
+	let mut chars = expression.char_indices().peekable();
+
+	while let Some((start, c)) = chars.next() {
+		let token = match c {
+			c if c.is_whitespace() => continue,
+			'(' => Token::OpenParen,
+			')' => Token::CloseParen,
+			',' => Token::Comma,
+			_ => {
+				let mut end = start + c.len_utf8();
+				while let Some((i, c)) = chars.peek() {
+					if !(c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '#')) {
+						break;
+					}
+					end = i + c.len_utf8();
+					chars.next();
+				}
+
+				let word = &expression[start..end];
+				if word.starts_with('#') {
+					Token::Color(Color::from_str(word).map_err(|err| err.to_string())?)
+				} else if c.is_ascii_digit() || matches!(c, '.' | '-') {
+					Token::Number(
+						word.parse()
+							.map_err(|_| format!("\"{word}\" is not a number"))?
+					)
+				} else if c.is_alphabetic() || c == '_' {
+					Token::Name(word.to_string())
+				} else {
+					return Err(format!("unexpected \"{c}\""));
+				}
+			}
+		};
+		tokens.push(token);
+	}
+
+	Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Value {
+	Color(Color),
+	Number(f32)
+}
+
+struct Parser<'a> {
+	tokens: Vec<Token>,
+	position: usize,
+	theme: &'a serde_json::Value
+}
+
+impl Parser<'_> {
+	fn next(&mut self) -> Option<Token> {
+		let token = self.tokens.get(self.position).cloned();
+		self.position += 1;
+		token
+	}
+
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.position)
+	}
+
+	fn expression(&mut self) -> Result<Value, String> {
+		match self.next() {
+			Some(Token::Color(color)) => Ok(Value::Color(color)),
+			Some(Token::Number(number)) => Ok(Value::Number(number)),
+			Some(Token::Name(name)) if self.peek() == Some(&Token::OpenParen) => {
+				self.next();
+				let arguments = self.arguments()?;
+				call(&name, &arguments).map(Value::Color)
+			}
+			Some(Token::Name(name)) => lookup(self.theme, &name)
+				.map(Value::Color)
+				.ok_or_else(|| format!("\"{name}\" is not a color of the theme")),
+			Some(token) => Err(format!("unexpected \"{token}\"")),
+			None => Err("unexpected end of expression".to_string())
+		}
+	}
+
+	/// Parses the arguments of a function call, after the opening parenthesis
+	fn arguments(&mut self) -> Result<Vec<Value>, String> {
+		let mut arguments = Vec::new();
+		if self.peek() == Some(&Token::CloseParen) {
+			self.next();
+			return Ok(arguments);
+		}
+
+		loop {
+			arguments.push(self.expression()?);
+			match self.next() {
+				Some(Token::Comma) => continue,
+				Some(Token::CloseParen) => return Ok(arguments),
+				Some(token) => return Err(format!("expected \",\" or \")\", found \"{token}\"")),
+				None => return Err("missing \")\"".to_string())
+			}
+		}
+	}
+}
+
+fn call(function: &str, arguments: &[Value]) -> Result<Color, String> {
+	use Value::{Color as C, Number as N};
+
+	let color = match (function, arguments) {
+		("lighten", [C(color), N(amount)]) => color.lighten(*amount),
+		("darken", [C(color), N(amount)]) => color.darken(*amount),
+		("shade", [C(color), N(lightness)]) => color.shade(*lightness),
+		("with_alpha", [C(color), N(alpha)]) => color.with_alpha(*alpha),
+		("mix", [C(a), C(b)]) => Color::mix(*a, *b),
+		("blend", [C(a), C(b), N(t)]) => Color::blend(*a, *b, *t),
+		("lighten" | "darken" | "shade" | "with_alpha", _) => {
+			return Err(format!("{function} takes a color and a number"));
+		}
+		("mix", _) => return Err("mix takes two colors".to_string()),
+		("blend", _) => return Err("blend takes two colors and a number".to_string()),
+		_ => return Err(format!("unknown function \"{function}\""))
+	};
+	Ok(color)
+}
+
+/// Evaluates a color expression, which is a color role like `terminal.blue`, a literal color
+/// like `#ff0000`, or a function of those like `lighten(ui.surface, 0.1)`
+fn evaluate(expression: &str, theme: &serde_json::Value) -> Result<Color, String> {
+	let mut parser = Parser {
+		tokens: tokenize(expression)?,
+		position: 0,
+		theme
+	};
+
+	let value = parser.expression()?;
+	if let Some(token) = parser.peek() {
+		return Err(format!("unexpected \"{token}\""));
+	}
+
+	match value {
+		Value::Color(color) => Ok(color),
+		Value::Number(_) => Err("the expression must result in a color".to_string())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+
+	fn theme() -> serde_json::Value {
+		json!({
+			"ui": { "surface": "#202020ff", "primary": "#ff0000ff" },
+			"terminal": { "blue": "#0000ffff" }
+		})
+	}
+
+	#[test]
+	fn should_evaluate_expressions() {
+		let theme = theme();
+
+		assert_eq!(
+			evaluate("terminal.blue", &theme),
+			Ok(Color::from(0x0000ffff))
+		);
+		assert_eq!(evaluate("primary", &theme), Ok(Color::from(0xff0000ff)));
+		assert_eq!(
+			evaluate("lighten(ui.surface, 0.1)", &theme),
+			Ok(Color::from(0x202020ff).lighten(0.1))
+		);
+		assert_eq!(
+			evaluate("blend(ui.primary, #0000ff, 0.5)", &theme),
+			Ok(Color::mix(Color::from(0xff0000ff), Color::from(0x0000ffff)))
+		);
+	}
+
+	#[test]
+	fn should_reject_invalid_expressions() {
+		let theme = theme();
+
+		assert!(evaluate("ui.nope", &theme).is_err());
+		assert!(evaluate("lighten(ui.surface)", &theme).is_err());
+		assert!(evaluate("lighten(ui.surface, 0.1", &theme).is_err());
+		assert!(evaluate("frobnicate(ui.surface)", &theme).is_err());
+		assert!(evaluate("0.5", &theme).is_err());
+		assert!(evaluate("ui.surface ui.primary", &theme).is_err());
+	}
+}
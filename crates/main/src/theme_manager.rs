@@ -1,11 +1,20 @@
-use std::{collections::HashSet, fs, io, path::PathBuf, rc::Rc};
+use std::{
+	collections::{hash_map::RandomState, BTreeMap},
+	fs,
+	hash::{BuildHasher, Hasher},
+	io,
+	path::{Path, PathBuf},
+	rc::Rc
+};
 
-use log::debug;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
-	config::{self, Theme},
-	files::Files
+	config::{self, ColorScheme, Theme, ThemeMeta},
+	files::Files,
+	theme::derive
 };
 
 #[derive(Debug, Error)]
@@ -23,7 +32,108 @@ pub enum Error {
 	UnknownCurrentTheme(String),
 
 	#[error("No theme is selected")]
-	NoThemeSelected
+	NoThemeSelected,
+
+	#[error("Theme \"{0}\" extends \"{1}\", but that theme doesn't exist!")]
+	UnknownParentTheme(String, String),
+
+	#[error("Theme \"{0}\" has circular inheritance: {1}")]
+	InheritanceCycle(String, String),
+
+	#[error("A custom theme named \"{0}\" already exists")]
+	ThemeExists(String),
+
+	#[error(
+		"\"{0}\" is not a valid theme name. Theme names can't be empty, and may only contain a \
+		 \"/\" to separate the family and the variant, like \"family/variant\""
+	)]
+	InvalidThemeName(String),
+
+	#[error("Failed to serialize theme \"{0}\": {1}")]
+	ThemeSerialize(String, toml::ser::Error),
+
+	#[error("Failed to write theme \"{0}\" to {1}: {2}")]
+	ThemeWrite(String, String, io::Error),
+
+	#[error("Theme \"{0}\" has no {1} variant")]
+	NoVariant(String, ColorScheme),
+
+	#[error("There is no previous theme to go back to")]
+	NoPreviousTheme,
+
+	#[error("No themes match the given filters")]
+	NoMatchingThemes,
+
+	#[error("There are no other themes to choose from")]
+	NoOtherTheme
+}
+
+/// Built-in themes that were renamed, as pairs of the old and the new name. The old names
+/// keep working, so that existing state and configs don't break.
+pub const RENAMED_THEMES: [(&str, &str); 3] = [
+	("catpuccin-frappe", "catppuccin/frappe"),
+	("catpuccin-latte", "catppuccin/latte"),
+	("gruvbox-light", "gruvbox/light")
+];
+
+/// The new name of a theme that was renamed
+pub fn renamed_theme(name: &str) -> Option<&'static str> {
+	RENAMED_THEMES
+		.iter()
+		.find(|(old, _)| *old == name)
+		.map(|(_, new)| *new)
+}
+
+/// How many themes the theme history keeps
+const HISTORY_LENGTH: usize = 100;
+
+/// How many of the most recently set themes a random theme is never picked from, as long as
+/// there are enough other themes
+const RANDOM_AVOID_RECENT: usize = 5;
+
+/// Selects themes by their color scheme and tags
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThemeFilter {
+	pub color_scheme: Option<ColorScheme>,
+
+	/// Tags that the themes must all have. Tags are compared case-insensitively.
+	pub tags: Vec<String>
+}
+
+impl ThemeFilter {
+	pub fn is_empty(&self) -> bool {
+		self.color_scheme.is_none() && self.tags.is_empty()
+	}
+
+	pub fn matches(&self, theme: &Theme) -> bool {
+		self.matches_metadata(&theme.ui.color_scheme, &theme.meta)
+	}
+
+	fn matches_metadata(&self, color_scheme: &ColorScheme, meta: &ThemeMeta) -> bool {
+		if self
+			.color_scheme
+			.as_ref()
+			.is_some_and(|filter_color_scheme| filter_color_scheme != color_scheme)
+		{
+			return false;
+		}
+
+		self.tags.iter().all(|tag| {
+			meta.tags
+				.iter()
+				.any(|theme_tag| theme_tag.eq_ignore_ascii_case(tag))
+		})
+	}
+}
+
+/// Where a theme was found
+#[derive(Debug)]
+pub struct ThemeLocation {
+	pub name: String,
+	/// The themes directory the theme is in
+	pub dir: PathBuf,
+	/// The paths of other themes with the same name, which are hidden by this theme
+	pub shadows: Vec<PathBuf>
 }
 
 pub struct ThemeManager {
@@ -31,22 +141,53 @@ pub struct ThemeManager {
 }
 
 impl ThemeManager {
+	const EXTENDS_KEY: &'static str = "extends";
+	const META_KEY: &'static str = "meta";
+
 	pub fn new(files: Rc<Files>) -> Self {
 		Self { files }
 	}
 
+	/// The names of all available themes, sorted alphabetically
 	pub fn list_themes(&self) -> Vec<String> {
-		let mut themes = HashSet::new();
+		self.find_themes()
+			.into_iter()
+			.map(|theme| theme.name)
+			.collect()
+	}
+
+	/// All available themes, sorted by name. Themes that are shadowed by a theme of the same
+	/// name in an earlier themes directory are left out.
+	pub fn find_themes(&self) -> Vec<ThemeLocation> {
+		let mut themes: BTreeMap<String, ThemeLocation> = BTreeMap::new();
 		for location in self.files.iter_themes() {
-			if themes.insert(location.name.clone()) {
-				debug!(
-					"Found theme {} at {}",
-					location.name,
-					location.path.display()
-				);
+			if let Some(theme) = themes.get_mut(&location.name) {
+				theme.shadows.push(location.path);
+				continue;
 			}
+
+			debug!(
+				"Found theme {} at {}",
+				location.name,
+				location.path.display()
+			);
+			let dir = self
+				.files
+				.themes_dirs()
+				.iter()
+				.find(|dir| location.path.starts_with(dir))
+				.cloned()
+				.unwrap_or_default();
+			themes.insert(
+				location.name.clone(),
+				ThemeLocation {
+					name: location.name,
+					dir,
+					shadows: Vec::new()
+				}
+			);
 		}
-		themes.into_iter().collect()
+		themes.into_values().collect()
 	}
 
 	pub fn current_theme(&self) -> Result<Theme, Error> {
@@ -54,13 +195,20 @@ impl ThemeManager {
 			self.unset_theme()?;
 		}
 
-		let current_theme =
+		let mut current_theme =
 			fs::read_to_string(self.files.current_theme_file()).map_err(Error::AccessThemeState)?;
 
 		if current_theme.is_empty() {
 			return Err(Error::NoThemeSelected);
 		}
 
+		if let Some(new_name) = self.migrate_name(&current_theme) {
+			info!("Theme \"{current_theme}\" was renamed to \"{new_name}\"");
+			fs::write(self.files.current_theme_file(), &new_name)
+				.map_err(Error::AccessThemeState)?;
+			current_theme = new_name;
+		}
+
 		let theme: Option<Theme> = self.read_theme(&current_theme)?;
 		let Some(mut theme) = theme else {
 			return Err(Error::UnknownCurrentTheme(current_theme));
@@ -72,18 +220,313 @@ impl ThemeManager {
 	}
 
 	pub fn get_theme(&self, name: &str) -> Result<Theme, Error> {
+		if let Some(new_name) = self.migrate_name(name) {
+			return self.get_theme(&new_name);
+		}
+
 		self.read_theme(name)?
 			.ok_or_else(|| Error::UnknownTheme(name.to_string()))
 	}
 
+	/// The new name of a built-in theme that was renamed, unless a theme with the old name
+	/// still exists
+	fn migrate_name(&self, name: &str) -> Option<String> {
+		let new_name = renamed_theme(name)?;
+		if self.find_theme_path(name).is_some() {
+			return None;
+		}
+		Some(new_name.to_string())
+	}
+
+	/// Resolves the name of the theme to set for `name`. If a color scheme is given, or `name`
+	/// is a family of themes rather than a theme itself, this is the variant of the family with
+	/// that color scheme. Without a color scheme, the variant with the color scheme of the
+	/// current theme is used.
+	pub fn resolve_theme_name(
+		&self,
+		name: &str,
+		color_scheme: Option<ColorScheme>
+	) -> Result<String, Error> {
+		if let Some(new_name) = self.migrate_name(name) {
+			return self.resolve_theme_name(&new_name, color_scheme);
+		}
+		if color_scheme.is_none() && self.find_theme_path(name).is_some() {
+			return Ok(name.to_string());
+		}
+
+		let color_scheme = match color_scheme {
+			Some(color_scheme) => color_scheme,
+			None => self
+				.current_theme()
+				.map_or(ColorScheme::Dark, |theme| theme.ui.color_scheme)
+		};
+		let family = match self.find_theme_path(name) {
+			Some(_) => family_of(name, &self.read_metadata(name)?.1),
+			None => family(name).to_string()
+		};
+		self.find_variant(&family, &color_scheme)
+	}
+
+	/// Switches the current theme to the variant of its family with the opposite color
+	/// scheme, and returns the name of that variant
+	pub fn toggle_scheme(&self) -> Result<String, Error> {
+		let current = self.current_theme()?;
+		let family = family_of(current.name.as_deref().unwrap_or_default(), &current.meta);
+		let color_scheme = match current.ui.color_scheme {
+			ColorScheme::Light => ColorScheme::Dark,
+			ColorScheme::Dark => ColorScheme::Light
+		};
+
+		let variant = self.find_variant(&family, &color_scheme)?;
+		self.set_theme(variant.clone())?;
+		Ok(variant)
+	}
+
+	/// Finds the variant of `family` with the given color scheme. A variant that is named
+	/// after the color scheme, like `gruvbox/light`, is preferred; otherwise the first
+	/// matching variant in alphabetical order is used. Only the metadata of the themes is
+	/// read, as themes outside of the family directory are only checked for their
+	/// `meta.family`.
+	fn find_variant(&self, family: &str, color_scheme: &ColorScheme) -> Result<String, Error> {
+		let prefix = format!("{family}/");
+
+		let mut found_family = false;
+		let mut variants = Vec::new();
+		for name in self.list_themes() {
+			let in_family_dir = name == family || name.starts_with(&prefix);
+			let (theme_color_scheme, meta) = match self.read_metadata(&name) {
+				Ok(metadata) => metadata,
+				Err(e) if in_family_dir => return Err(e),
+				// Broken themes outside of the family directory can't be part of it
+				Err(_) => continue
+			};
+			if !in_family_dir && meta.family.as_deref() != Some(family) {
+				continue;
+			}
+
+			found_family = true;
+			if theme_color_scheme == *color_scheme {
+				variants.push(name);
+			}
+		}
+		if !found_family {
+			return Err(Error::UnknownTheme(family.to_string()));
+		}
+
+		let preferred = format!("{prefix}{color_scheme}");
+		let variant = variants
+			.iter()
+			.find(|name| **name == preferred)
+			.or(variants.first())
+			.cloned()
+			.ok_or_else(|| Error::NoVariant(family.to_string(), color_scheme.clone()))?;
+
+		debug!("Using the {color_scheme} variant \"{variant}\" of \"{family}\"");
+		Ok(variant)
+	}
+
+	/// Sets the current theme, and adds it to the theme history
 	pub fn set_theme(&self, name: String) -> Result<(), Error> {
 		if self.find_theme_path(&name).is_none() {
 			return Err(Error::UnknownTheme(name));
 		}
-		fs::write(self.files.current_theme_file(), name).map_err(Error::AccessThemeState)?;
+
+		let previous = self.current_theme_name()?;
+		fs::write(self.files.current_theme_file(), &name).map_err(Error::AccessThemeState)?;
+
+		let mut history = self.read_history()?;
+		if record_theme_change(&mut history, previous, name) {
+			self.write_history(&history)?;
+		}
 		Ok(())
 	}
 
+	/// The name of the current theme, without reading the theme itself
+	fn current_theme_name(&self) -> Result<Option<String>, Error> {
+		match fs::read_to_string(self.files.current_theme_file()) {
+			Ok(name) if name.trim().is_empty() => Ok(None),
+			Ok(name) => Ok(Some(name.trim().to_string())),
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+			Err(err) => Err(Error::AccessThemeState(err))
+		}
+	}
+
+	fn read_history(&self) -> Result<Vec<String>, Error> {
+		match fs::read_to_string(self.files.theme_history_file()) {
+			Ok(history) => Ok(history
+				.lines()
+				.filter(|line| !line.is_empty())
+				.map(str::to_string)
+				.collect()),
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+			Err(err) => Err(Error::AccessThemeState(err))
+		}
+	}
+
+	fn write_history(&self, history: &[String]) -> Result<(), Error> {
+		let start = history.len().saturating_sub(HISTORY_LENGTH);
+		let mut contents = history[start..].join("\n");
+		contents.push('\n');
+		fs::write(self.files.theme_history_file(), contents).map_err(Error::AccessThemeState)
+	}
+
+	/// Goes back to the theme that was set before the current one, and returns its name.
+	/// Themes that no longer exist are skipped.
+	pub fn previous_theme(&self) -> Result<String, Error> {
+		let current = self.current_theme_name()?;
+		let mut history = self.read_history()?;
+
+		if history
+			.last()
+			.is_some_and(|last| Some(last) == current.as_ref())
+		{
+			history.pop();
+		}
+		while let Some(last) = history.last() {
+			if Some(last) != current.as_ref() && self.find_theme_path(last).is_some() {
+				break;
+			}
+			history.pop();
+		}
+
+		let Some(previous) = history.last().cloned() else {
+			return Err(Error::NoPreviousTheme);
+		};
+		fs::write(self.files.current_theme_file(), &previous).map_err(Error::AccessThemeState)?;
+		self.write_history(&history)?;
+		Ok(previous)
+	}
+
+	/// The names of the themes that match `filter`, sorted alphabetically. Only the metadata
+	/// of the themes is read, and only if the filter is set. Themes whose metadata can't be
+	/// read don't match any filter.
+	fn filter_themes(&self, filter: &ThemeFilter) -> Vec<String> {
+		let themes = self.list_themes();
+		if filter.is_empty() {
+			return themes;
+		}
+
+		themes
+			.into_iter()
+			.filter(|name| match self.read_metadata(name) {
+				Ok((color_scheme, meta)) => filter.matches_metadata(&color_scheme, &meta),
+				Err(err) => {
+					debug!("Skipping theme \"{name}\": {err}");
+					false
+				}
+			})
+			.collect()
+	}
+
+	/// Reads the color scheme and the metadata of a theme, without reading its colors
+	fn read_metadata(&self, name: &str) -> Result<(ColorScheme, ThemeMeta), Error> {
+		#[derive(Deserialize)]
+		struct UiMetadata {
+			color_scheme: ColorScheme
+		}
+
+		#[derive(Deserialize)]
+		struct Metadata {
+			#[serde(default)]
+			meta: ThemeMeta,
+			ui: UiMetadata
+		}
+
+		let path = self
+			.find_theme_path(name)
+			.ok_or_else(|| Error::UnknownTheme(name.to_string()))?;
+		let (table, _) = self.resolve_theme_table(name, &path, &mut Vec::new())?;
+
+		let metadata: Metadata = table.try_into().map_err(|e| {
+			Error::ThemeRead(
+				name.to_string(),
+				config::Error::Parse(path.display().to_string(), Box::new(e))
+			)
+		})?;
+		Ok((metadata.ui.color_scheme, metadata.meta))
+	}
+
+	/// Switches to the next (or previous, if `forward` is false) theme that matches `filter`,
+	/// in alphabetical order, and returns its name
+	pub fn cycle_theme(&self, filter: &ThemeFilter, forward: bool) -> Result<String, Error> {
+		let themes = self.filter_themes(filter);
+		if themes.is_empty() {
+			return Err(Error::NoMatchingThemes);
+		}
+
+		let current = self.current_theme_name()?;
+		let position = themes
+			.iter()
+			.position(|name| Some(name) == current.as_ref());
+		let len = themes.len();
+		let index = match (position, forward) {
+			(Some(position), true) => (position + 1) % len,
+			(Some(position), false) => (position + len - 1) % len,
+			(None, true) => 0,
+			(None, false) => len - 1
+		};
+
+		let name = themes[index].clone();
+		self.set_theme(name.clone())?;
+		Ok(name)
+	}
+
+	/// Switches to a random theme that matches `filter`, and returns its name. The current
+	/// theme and the most recently set themes are avoided.
+	pub fn random_theme(&self, filter: &ThemeFilter) -> Result<String, Error> {
+		let themes = self.filter_themes(filter);
+		if themes.is_empty() {
+			return Err(Error::NoMatchingThemes);
+		}
+
+		let current = self.current_theme_name()?;
+		let history = self.read_history()?;
+		let recent: Vec<&String> = history.iter().rev().take(RANDOM_AVOID_RECENT).collect();
+
+		let others: Vec<&String> = themes
+			.iter()
+			.filter(|name| Some(*name) != current.as_ref())
+			.collect();
+		let fresh: Vec<&String> = others
+			.iter()
+			.copied()
+			.filter(|name| !recent.contains(name))
+			.collect();
+		let candidates = if fresh.is_empty() { others } else { fresh };
+		if candidates.is_empty() {
+			return Err(Error::NoOtherTheme);
+		}
+
+		// The hasher of a new `RandomState` is randomly seeded, which is random enough to
+		// pick a theme
+		let random = RandomState::new().build_hasher().finish();
+		let name = candidates[(random % candidates.len() as u64) as usize].clone();
+		self.set_theme(name.clone())?;
+		Ok(name)
+	}
+
+	/// Saves `theme` as a custom theme, and returns the path of the created theme file.
+	pub fn save_theme(&self, name: &str, theme: &Theme) -> Result<PathBuf, Error> {
+		if !is_valid_theme_name(name) {
+			return Err(Error::InvalidThemeName(name.to_string()));
+		}
+
+		let path = self.files.custom_theme_file(name);
+		if path.exists() {
+			return Err(Error::ThemeExists(name.to_string()));
+		}
+
+		let source =
+			toml::to_string(theme).map_err(|e| Error::ThemeSerialize(name.to_string(), e))?;
+
+		let write_error = |e| Error::ThemeWrite(name.to_string(), path.display().to_string(), e);
+		fs::create_dir_all(path.parent().unwrap()).map_err(write_error)?;
+		fs::write(&path, source).map_err(write_error)?;
+
+		debug!("Saved theme \"{name}\" to {}", path.display());
+		Ok(path)
+	}
+
 	pub fn unset_theme(&self) -> Result<(), Error> {
 		fs::write(self.files.current_theme_file(), "").map_err(Error::AccessThemeState)?;
 		Ok(())
@@ -104,13 +547,241 @@ impl ThemeManager {
 			return Ok(None);
 		};
 
-		debug!("Reading theme \"{name}\" from {}", path.display());
+		let (mut table, inherited) = self.resolve_theme_table(name, &path, &mut Vec::new())?;
+		let derived = derive::fill_derived(&mut table);
 
-		let mut theme: Theme =
-			config::read(path).map_err(|e| Error::ThemeRead(name.to_string(), e))?;
+		let mut theme: Theme = table.try_into().map_err(|e| {
+			Error::ThemeRead(
+				name.to_string(),
+				config::Error::Parse(path.display().to_string(), Box::new(e))
+			)
+		})?;
 
 		theme.name = Some(name.to_string());
+		theme.terminal.palette_256 = derive::palette_256(&theme.terminal);
+		theme.inherited = inherited;
+		theme.derived = derived;
 
 		Ok(Some(theme))
 	}
+
+	/// Reads the theme file at `path` and merges it with the themes it extends. Returns the
+	/// merged table, as well as the theme each inherited value comes from.
+	fn resolve_theme_table(
+		&self,
+		name: &str,
+		path: &Path,
+		chain: &mut Vec<(String, PathBuf)>
+	) -> Result<(toml::Table, BTreeMap<String, String>), Error> {
+		debug!("Reading theme \"{name}\" from {}", path.display());
+
+		chain.push((name.to_string(), path.to_path_buf()));
+
+		let table: toml::Table =
+			config::read(path).map_err(|e| Error::ThemeRead(name.to_string(), e))?;
+
+		let Some(parent) = table.get(Self::EXTENDS_KEY).and_then(toml::Value::as_str) else {
+			return Ok((table, BTreeMap::new()));
+		};
+
+		// A theme may extend a theme with the same name that it shadows, so themes are
+		// matched by path to detect cycles.
+		let mut parent_locations = self.files.iter_themes().filter(|l| l.name == parent);
+		let Some(parent_path) = parent_locations
+			.find(|l| !chain.iter().any(|(_, path)| *path == l.path))
+			.map(|l| l.path)
+		else {
+			if self.find_theme_path(parent).is_some() {
+				let cycle = chain
+					.iter()
+					.map(|(name, _)| name.as_str())
+					.chain([parent])
+					.collect::<Vec<_>>()
+					.join(" -> ");
+				return Err(Error::InheritanceCycle(chain[0].0.clone(), cycle));
+			}
+			return Err(Error::UnknownParentTheme(
+				name.to_string(),
+				parent.to_string()
+			));
+		};
+
+		let parent = parent.to_string();
+		let (mut merged, mut inherited) = self.resolve_theme_table(&parent, &parent_path, chain)?;
+
+		// The metadata describes the parent theme, not the themes extending it
+		merged.remove(Self::META_KEY);
+		inherited.retain(|key, _| !key.starts_with(&format!("{}.", Self::META_KEY)));
+
+		for key in leaf_keys(&merged, "") {
+			inherited.entry(key).or_insert_with(|| parent.clone());
+		}
+		merge_tables(&mut merged, table, "", &mut inherited);
+
+		Ok((merged, inherited))
+	}
+}
+
+/// The family a theme belongs to, like `catppuccin` for `catppuccin/latte`. Themes that aren't
+/// variants form a family of their own.
+fn family(name: &str) -> &str {
+	name.split_once('/').map_or(name, |(family, _)| family)
+}
+
+/// The family of the theme `name`, which may be set explicitly in its metadata
+fn family_of(name: &str, meta: &ThemeMeta) -> String {
+	meta.family
+		.clone()
+		.unwrap_or_else(|| family(name).to_string())
+}
+
+fn leaf_keys(table: &toml::Table, prefix: &str) -> Vec<String> {
+	let mut keys = Vec::new();
+	for (key, value) in table {
+		if prefix.is_empty() && key == ThemeManager::EXTENDS_KEY {
+			continue;
+		}
+
+		let key = format!("{prefix}{key}");
+		match value {
+			toml::Value::Table(table) => keys.extend(leaf_keys(table, &format!("{key}."))),
+			_ => keys.push(key)
+		}
+	}
+	keys
+}
+
+/// Recursively merges `overrides` into `base`, and removes all keys that are overridden
+/// from `inherited`.
+fn merge_tables(
+	base: &mut toml::Table,
+	overrides: toml::Table,
+	prefix: &str,
+	inherited: &mut BTreeMap<String, String>
+) {
+	for (key, value) in overrides {
+		let full_key = format!("{prefix}{key}");
+
+		match (base.get_mut(&key), value) {
+			(Some(toml::Value::Table(base_table)), toml::Value::Table(override_table)) => {
+				merge_tables(
+					base_table,
+					override_table,
+					&format!("{full_key}."),
+					inherited
+				);
+			}
+			(_, value) => {
+				let nested_prefix = format!("{full_key}.");
+				inherited.retain(|k, _| *k != full_key && !k.starts_with(&nested_prefix));
+				base.insert(key, value);
+			}
+		}
+	}
+}
+
+/// Whether `name` can be used for a custom theme. Names are used as paths in the themes
+/// directory, so they may not escape it, and may contain one `/` to name a variant of a theme
+/// family. The variant can't be called `theme`, as that file holds the family's own theme.
+fn is_valid_theme_name(name: &str) -> bool {
+	let parts: Vec<&str> = name.split('/').collect();
+	let valid_parts = parts.iter().all(|part| {
+		!part.is_empty() && *part != "." && *part != ".." && !part.contains(['\\', '\0'])
+	});
+
+	match parts[..] {
+		[_] => valid_parts,
+		[_, variant] => valid_parts && variant != "theme",
+		_ => false
+	}
+}
+
+/// Records a change from the theme `previous` to `name` in `history`, and returns whether
+/// `history` changed. The previous theme is added first if it isn't the last entry, which is
+/// the case when it was set before the history was kept.
+fn record_theme_change(history: &mut Vec<String>, previous: Option<String>, name: String) -> bool {
+	if previous.as_ref() == Some(&name) {
+		return false;
+	}
+
+	if let Some(previous) = previous {
+		if history.last() != Some(&previous) {
+			history.push(previous);
+		}
+	}
+	history.push(name);
+	true
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_track_inherited_values() {
+		let mut base: toml::Table = toml::from_str(
+			"[ui]\nprimary = \"#ff0000\"\nsecondary = \"#00ff00\"\n[terminal]\nred = \"#ff0000\""
+		)
+		.unwrap();
+		let overrides: toml::Table =
+			toml::from_str("extends = \"base\"\n[ui]\nprimary = \"#0000ff\"").unwrap();
+
+		let mut inherited = BTreeMap::new();
+		for key in leaf_keys(&base, "") {
+			inherited.insert(key, "base".to_string());
+		}
+		merge_tables(&mut base, overrides, "", &mut inherited);
+
+		assert_eq!(base["ui"]["primary"].as_str(), Some("#0000ff"));
+		assert_eq!(base["ui"]["secondary"].as_str(), Some("#00ff00"));
+		assert_eq!(
+			inherited.keys().collect::<Vec<_>>(),
+			vec!["terminal.red", "ui.secondary"]
+		);
+	}
+
+	#[test]
+	fn should_validate_theme_names() {
+		assert!(is_valid_theme_name("mine"));
+		assert!(is_valid_theme_name("mine/dark"));
+
+		assert!(!is_valid_theme_name(""));
+		assert!(!is_valid_theme_name(".."));
+		assert!(!is_valid_theme_name("../x"));
+		assert!(!is_valid_theme_name("mine/"));
+		assert!(!is_valid_theme_name("/mine"));
+		assert!(!is_valid_theme_name("mine/dark/hard"));
+		assert!(!is_valid_theme_name("mine/theme"));
+	}
+
+	#[test]
+	fn should_record_theme_changes() {
+		let mut history = Vec::new();
+		assert!(record_theme_change(
+			&mut history,
+			Some("dracula".to_string()),
+			"gruvbox".to_string()
+		));
+		assert_eq!(history, vec!["dracula", "gruvbox"]);
+
+		assert!(!record_theme_change(
+			&mut history,
+			Some("gruvbox".to_string()),
+			"gruvbox".to_string()
+		));
+		assert!(record_theme_change(
+			&mut history,
+			Some("gruvbox".to_string()),
+			"tokyonight".to_string()
+		));
+		assert_eq!(history, vec!["dracula", "gruvbox", "tokyonight"]);
+
+		let mut history = Vec::new();
+		assert!(record_theme_change(
+			&mut history,
+			None,
+			"dracula".to_string()
+		));
+		assert_eq!(history, vec!["dracula"]);
+	}
 }
@@ -54,11 +54,92 @@ impl Color {
 		Self::new_rgba(self.r, self.g, self.b, (alpha * 255.0) as u8)
 	}
 
-	fn into_oklch(self) -> OklchColor {
+	/// The relative luminance as defined by WCAG 2.x, ranging from 0 (black) to 1 (white)
+	pub fn relative_luminance(self) -> f32 {
+		fn linearize(channel: u8) -> f32 {
+			let channel = channel as f32 / 255.0;
+			if channel <= 0.04045 {
+				channel / 12.92
+			} else {
+				((channel + 0.055) / 1.055).powf(2.4)
+			}
+		}
+
+		0.2126 * linearize(self.r) + 0.7152 * linearize(self.g) + 0.0722 * linearize(self.b)
+	}
+
+	/// The WCAG 2.x contrast ratio between two colors, ranging from 1 to 21
+	pub fn contrast_ratio(col1: Self, col2: Self) -> f32 {
+		let lum1 = col1.relative_luminance();
+		let lum2 = col2.relative_luminance();
+
+		(lum1.max(lum2) + 0.05) / (lum1.min(lum2) + 0.05)
+	}
+
+	/// The APCA lightness contrast (Lc) of `text` on `background`, as defined by APCA-W3
+	/// 0.0.98G. Ranges from about -108 to 106; negative values mean light text on a dark
+	/// background.
+	pub fn apca_contrast(text: Self, background: Self) -> f32 {
+		fn luminance(color: Color) -> f32 {
+			let channel = |value: u8| (value as f32 / 255.0).powf(2.4);
+			let y = 0.2126729 * channel(color.r)
+				+ 0.7151522 * channel(color.g)
+				+ 0.0721750 * channel(color.b);
+
+			// Soft clamp for very dark colors
+			if y < 0.022 {
+				y + (0.022 - y).powf(1.414)
+			} else {
+				y
+			}
+		}
+
+		let text = luminance(text);
+		let background = luminance(background);
+		if (background - text).abs() < 0.0005 {
+			return 0.0;
+		}
+
+		let contrast = if background > text {
+			let s = (background.powf(0.56) - text.powf(0.57)) * 1.14;
+			if s < 0.1 {
+				0.0
+			} else {
+				s - 0.027
+			}
+		} else {
+			let s = (background.powf(0.65) - text.powf(0.62)) * 1.14;
+			if s > -0.1 {
+				0.0
+			} else {
+				s + 0.027
+			}
+		};
+
+		contrast * 100.0
+	}
+
+	/// Picks a near-white or near-black text color, tinted with the hue of this color, that
+	/// has the best contrast when placed on top of this color.
+	pub fn readable_text(self) -> Self {
+		let background = self.into_oklch();
+		let chroma = background.chroma().min(0.02);
+
+		let light = Self::from_oklch(OklchColor::new(0.96, chroma, background.hue()), 0xff);
+		let dark = Self::from_oklch(OklchColor::new(0.2, chroma, background.hue()), 0xff);
+
+		if Self::contrast_ratio(self, light) >= Self::contrast_ratio(self, dark) {
+			light
+		} else {
+			dark
+		}
+	}
+
+	pub fn into_oklch(self) -> OklchColor {
 		OklchColor::from_srgb(self.r, self.g, self.b)
 	}
 
-	fn from_oklch(color: OklchColor, a: u8) -> Self {
+	pub fn from_oklch(color: OklchColor, a: u8) -> Self {
 		let (r, g, b) = color.into_srgb();
 		Self::new_rgba(r, g, b, a)
 	}
@@ -157,4 +238,29 @@ mod test {
 	fn should_parse_8_len() {
 		assert_eq!(Color::from_str("#abcdef80"), Ok(Color::from(0xabcdef80)));
 	}
+
+	#[test]
+	fn should_calculate_contrast_ratio() {
+		let black = Color::from(0x000000ff);
+		let white = Color::from(0xffffffff);
+
+		assert!((Color::contrast_ratio(black, white) - 21.0).abs() < 0.01);
+		assert!((Color::contrast_ratio(white, white) - 1.0).abs() < 0.01);
+	}
+
+	#[test]
+	fn should_calculate_apca_contrast() {
+		let black = Color::from(0x000000ff);
+		let white = Color::from(0xffffffff);
+
+		assert!((Color::apca_contrast(black, white) - 106.04).abs() < 0.1);
+		assert!((Color::apca_contrast(white, black) + 107.88).abs() < 0.1);
+		assert_eq!(Color::apca_contrast(white, white), 0.0);
+	}
+
+	#[test]
+	fn should_pick_readable_text() {
+		assert!(Color::from(0x282828ff).readable_text().relative_luminance() > 0.5);
+		assert!(Color::from(0xfbf1c7ff).readable_text().relative_luminance() < 0.5);
+	}
 }
@@ -1,7 +1,12 @@
+use std::f32::consts::{PI, TAU};
+
 use oklab::{oklab_to_srgb, srgb_to_oklab, Oklab, Rgb};
 
 use crate::utils::lerp;
 
+/// Below this chroma, colors are practically gray and their hue is meaningless
+const ACHROMATIC_CHROMA: f32 = 0.002;
+
 #[derive(Debug, Clone, Copy)]
 pub struct OklchColor {
 	l: f32,
@@ -15,8 +20,10 @@ impl OklchColor {
 	}
 
 	pub fn from_srgb(r: u8, g: u8, b: u8) -> Self {
-		let lab = srgb_to_oklab(Rgb { r, g, b });
+		Self::from_oklab(srgb_to_oklab(Rgb { r, g, b }))
+	}
 
+	pub fn from_oklab(lab: Oklab) -> Self {
 		// Convert to polar representation
 		let c = f32::sqrt(lab.a.powi(2) + lab.b.powi(2));
 		let h = f32::atan2(lab.b, lab.a);
@@ -56,6 +63,18 @@ impl OklchColor {
 		result
 	}
 
+	pub fn with_chroma(self, chroma: f32) -> Self {
+		let mut result = self;
+		result.c = chroma;
+		result
+	}
+
+	pub fn with_hue(self, hue: f32) -> Self {
+		let mut result = self;
+		result.h = hue;
+		result
+	}
+
 	pub fn lighten(self, amount: f32) -> Self {
 		self.shade(self.lightness() + amount)
 	}
@@ -64,13 +83,38 @@ impl OklchColor {
 		self.shade(self.lightness() - amount)
 	}
 
+	/// The euclidean distance between two colors in the OKLab color space, which roughly
+	/// corresponds to how different they look
+	pub fn distance(col1: Self, col2: Self) -> f32 {
+		let (a1, b1) = (col1.c * f32::cos(col1.h), col1.c * f32::sin(col1.h));
+		let (a2, b2) = (col2.c * f32::cos(col2.h), col2.c * f32::sin(col2.h));
+
+		f32::sqrt((col1.l - col2.l).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2))
+	}
+
 	pub fn blend(col1: Self, col2: Self, t: f32) -> Self {
 		Self::new(
 			lerp(col1.lightness(), col2.lightness(), t),
 			lerp(col1.chroma(), col2.chroma(), t),
-			lerp(col1.hue(), col2.hue(), t)
+			Self::blend_hue(col1, col2, t)
 		)
 	}
+
+	/// Interpolates the hue along the shorter way around the hue circle. The hue of a gray
+	/// color is ignored, so that blending with gray doesn't shift the hue of the other color.
+	fn blend_hue(col1: Self, col2: Self, t: f32) -> f32 {
+		match (
+			col1.chroma() < ACHROMATIC_CHROMA,
+			col2.chroma() < ACHROMATIC_CHROMA
+		) {
+			(true, false) => col2.hue(),
+			(false, true) | (true, true) => col1.hue(),
+			(false, false) => {
+				let difference = (col2.hue() - col1.hue() + PI).rem_euclid(TAU) - PI;
+				(col1.hue() + difference * t + PI).rem_euclid(TAU) - PI
+			}
+		}
+	}
 }
 
 #[cfg(test)]
@@ -83,4 +127,25 @@ mod tests {
 
 		assert_eq!(color.into_srgb(), (174, 49, 39))
 	}
+
+	#[test]
+	fn should_blend_hue_along_the_shorter_arc() {
+		let a = OklchColor::new(0.5, 0.1, 3.0);
+		let b = OklchColor::new(0.5, 0.1, -3.0);
+
+		let middle = OklchColor::blend(a, b, 0.5);
+		assert!(PI - middle.hue().abs() < 1e-4, "{}", middle.hue());
+
+		let quarter = OklchColor::blend(a, b, 0.25);
+		assert!((quarter.hue() - (3.0 + (TAU - 6.0) / 4.0)).abs() < 1e-4);
+	}
+
+	#[test]
+	fn should_ignore_hue_of_gray() {
+		let gray = OklchColor::new(0.5, 0.0, 0.0);
+		let red = OklchColor::new(0.6, 0.2, 0.5);
+
+		assert_eq!(OklchColor::blend(gray, red, 0.3).hue(), 0.5);
+		assert_eq!(OklchColor::blend(red, gray, 0.3).hue(), 0.5);
+	}
 }
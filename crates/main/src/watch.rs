@@ -0,0 +1,297 @@
+use std::{
+	collections::{HashMap, HashSet},
+	ffi::OsStr,
+	fs,
+	io::{self, ErrorKind},
+	path::{Path, PathBuf},
+	thread,
+	time::Duration
+};
+
+use inotify::{EventMask, Events, Inotify, WatchDescriptor, WatchMask};
+use log::{debug, error, info, warn};
+use thiserror::Error;
+
+use crate::{
+	app::{self, NijiApp},
+	config::ModuleConfig
+};
+
+/// How long to wait for further changes before applying. Editors often save a file in
+/// several steps, and a theme change usually touches more than one file.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error("Failed to watch for changes: {0}")]
+	Inotify(#[from] io::Error),
+
+	#[error("{0}")]
+	App(#[from] app::Error)
+}
+
+/// What a watched directory belongs to
+#[derive(Debug, Clone)]
+enum Target {
+	Config,
+	Themes,
+	Module(String)
+}
+
+/// The changes seen in a batch of events
+#[derive(Debug, Default)]
+struct Changes {
+	config: bool,
+	themes: bool,
+	modules: HashSet<String>
+}
+
+struct Watcher {
+	inotify: Inotify,
+	/// The watched directories and what they belong to
+	targets: HashMap<WatchDescriptor, (PathBuf, Target)>,
+	/// The modules whose directories are watched
+	modules: HashSet<String>,
+	config_file_name: PathBuf
+}
+
+impl Watcher {
+	/// Watches the config file, the themes directories and the directories of the active
+	/// modules, including all of their subdirectories
+	fn new(app: &NijiApp) -> Result<Self, Error> {
+		let mut watcher = Self {
+			inotify: Inotify::init()?,
+			targets: HashMap::new(),
+			modules: HashSet::new(),
+			config_file_name: app.config_file().file_name().unwrap_or_default().into()
+		};
+
+		// Many editors replace the config file instead of writing to it, which a watch on the
+		// file itself wouldn't survive
+		if let Some(config_dir) = app.config_file().parent() {
+			watcher.add(config_dir, Target::Config)?;
+		}
+		for themes_dir in app.themes_dirs() {
+			watcher.add_recursive(themes_dir, Target::Themes)?;
+		}
+		watcher.add_modules(app)?;
+
+		Ok(watcher)
+	}
+
+	/// Watches the directories of the active modules that aren't watched yet
+	fn add_modules(&mut self, app: &NijiApp) -> Result<(), Error> {
+		for (name, module_dir) in app.active_module_dirs() {
+			if self.modules.insert(name.clone()) {
+				self.add_recursive(&module_dir, Target::Module(name))?;
+			}
+		}
+		Ok(())
+	}
+
+	fn add(&mut self, dir: &Path, target: Target) -> Result<(), Error> {
+		let mask = WatchMask::CLOSE_WRITE
+			| WatchMask::CREATE
+			| WatchMask::DELETE
+			| WatchMask::MOVED_FROM
+			| WatchMask::MOVED_TO;
+
+		match self.inotify.watches().add(dir, mask) {
+			Ok(wd) => {
+				debug!("Watching {} for {target:?}", dir.display());
+				self.targets.insert(wd, (dir.to_path_buf(), target));
+				Ok(())
+			}
+			Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+			Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+				warn!("Can't watch {}: {err}", dir.display());
+				Ok(())
+			}
+			Err(err) => Err(err.into())
+		}
+	}
+
+	fn add_recursive(&mut self, dir: &Path, target: Target) -> Result<(), Error> {
+		self.add(dir, target.clone())?;
+
+		let Ok(entries) = fs::read_dir(dir) else {
+			return Ok(());
+		};
+		for entry in entries.flatten() {
+			let path = entry.path();
+			if path.is_dir() && !is_ignored(&entry.file_name()) {
+				self.add_recursive(&path, target.clone())?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Blocks until something changes, and returns all changes made until nothing has
+	/// changed for `DEBOUNCE`
+	fn wait(&mut self) -> Result<Changes, Error> {
+		let mut buffer = [0; 4096];
+		let mut changes = Changes::default();
+
+		let events = self.inotify.read_events_blocking(&mut buffer)?;
+		self.record(events, &mut changes)?;
+
+		loop {
+			thread::sleep(DEBOUNCE);
+			match self.inotify.read_events(&mut buffer) {
+				Ok(events) => {
+					if !self.record(events, &mut changes)? {
+						break;
+					}
+				}
+				Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+				Err(err) => return Err(err.into())
+			}
+		}
+
+		Ok(changes)
+	}
+
+	/// Adds `events` to `changes`, and returns whether there were any events. Directories
+	/// created in watched directories are watched as well.
+	fn record(&mut self, events: Events, changes: &mut Changes) -> Result<bool, Error> {
+		let mut any = false;
+		let mut new_dirs = Vec::new();
+		for event in events {
+			any = true;
+
+			// The watch was removed, because the directory was deleted
+			if event.mask.contains(EventMask::IGNORED) {
+				self.targets.remove(&event.wd);
+				continue;
+			}
+
+			let Some((dir, target)) = self.targets.get(&event.wd) else {
+				continue;
+			};
+			let name = event.name.unwrap_or_default();
+			if is_ignored(name) {
+				continue;
+			}
+
+			if event.mask.contains(EventMask::ISDIR)
+				&& event
+					.mask
+					.intersects(EventMask::CREATE | EventMask::MOVED_TO)
+				&& !matches!(target, Target::Config)
+			{
+				new_dirs.push((dir.join(name), target.clone()));
+			}
+
+			match target {
+				Target::Config => changes.config |= name == self.config_file_name,
+				Target::Themes => changes.themes = true,
+				Target::Module(module) => {
+					changes.modules.insert(module.clone());
+				}
+			}
+		}
+
+		for (dir, target) in new_dirs {
+			self.add_recursive(&dir, target)?;
+		}
+		Ok(any)
+	}
+}
+
+/// Hidden files, and the backup and swap files editors create next to the files they edit
+fn is_ignored(name: &OsStr) -> bool {
+	let name = name.to_string_lossy();
+	name.starts_with('.') || name.ends_with('~') || name.ends_with(".swp") || name == "4913"
+}
+
+/// The state of everything modules are applied with, used to find out which modules are
+/// affected by a change
+struct Snapshot {
+	/// The themes the active modules are applied with, which differ from the current theme
+	/// for modules with a pinned theme or if an accent color is set
+	themes: HashMap<String, Option<serde_json::Value>>,
+	module_configs: HashMap<String, ModuleConfig>
+}
+
+impl Snapshot {
+	fn take(app: &NijiApp) -> Self {
+		Self {
+			themes: app
+				.active_modules()
+				.iter()
+				.map(|module| {
+					let theme = app
+						.module_theme(module)
+						.ok()
+						.and_then(|theme| serde_json::to_value(theme).ok());
+					(module.clone(), theme)
+				})
+				.collect(),
+			module_configs: app
+				.active_modules()
+				.iter()
+				.map(|module| (module.clone(), app.module_config(module)))
+				.collect()
+		}
+	}
+}
+
+/// The active modules that need to be applied again: the modules whose theme, files or
+/// config changed. A module's theme can also change with the config, for example when it's
+/// pinned to another theme or the accent color changes.
+fn affected_modules(
+	app: &NijiApp,
+	before: &Snapshot,
+	after: &Snapshot,
+	changes: &Changes
+) -> Vec<String> {
+	app.active_modules()
+		.iter()
+		.filter(|module| {
+			((changes.config || changes.themes)
+				&& before.themes.get(*module) != after.themes.get(*module))
+				|| changes.modules.contains(*module)
+				|| before.module_configs.get(*module) != after.module_configs.get(*module)
+		})
+		.cloned()
+		.collect()
+}
+
+/// Watches the config, the themes and the active modules, and applies the modules affected
+/// by each change
+pub fn run(reload: bool) -> Result<(), Error> {
+	let mut app = NijiApp::init()?;
+	let mut snapshot = Snapshot::take(&app);
+	let mut watcher = Watcher::new(&app)?;
+
+	info!("Watching for changes...");
+	loop {
+		let changes = watcher.wait()?;
+		debug!("Detected changes: {changes:?}");
+
+		if changes.config {
+			match NijiApp::init() {
+				Ok(new_app) => app = new_app,
+				Err(err) => {
+					error!("Failed to reload the config: {err}");
+					continue;
+				}
+			}
+			watcher.add_modules(&app)?;
+		}
+
+		let new_snapshot = Snapshot::take(&app);
+		let modules = affected_modules(&app, &snapshot, &new_snapshot, &changes);
+		snapshot = new_snapshot;
+
+		if modules.is_empty() {
+			debug!("No modules are affected");
+			continue;
+		}
+
+		info!("Applying {}", modules.join(", "));
+		if let Err(err) = app.apply(reload, false, Some(&modules)) {
+			error!("{err}");
+		}
+	}
+}